@@ -0,0 +1,79 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use day_09::{Direction, Rope, Vec2};
+
+const STEPS: usize = 2_000_000;
+const KNOTS: usize = 10;
+
+/// Deterministic pseudo-random walk, so both variants see the same moves.
+fn directions(steps: usize) -> Vec<Direction> {
+    let mut state: u32 = 0x2545_f491;
+
+    (0..steps)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+
+            match state % 4 {
+                0 => Direction::Up,
+                1 => Direction::Down,
+                2 => Direction::Left,
+                _ => Direction::Right,
+            }
+        })
+        .collect()
+}
+
+/// The previous follow rule, based on the euclidean distance.
+fn step_float(knots: &mut [Vec2], direction: Direction) {
+    knots[0] = knots[0] + direction.offset();
+
+    for i in 1..knots.len() {
+        let head = knots[i - 1];
+        let next = &mut knots[i];
+
+        let distance = ((head.x - next.x).pow(2) as f32 + (head.y - next.y).pow(2) as f32).sqrt();
+
+        if distance >= 2f32 {
+            *next = *next + (head - *next).cap();
+        }
+    }
+}
+
+fn bench_rope(c: &mut Criterion) {
+    let directions = directions(STEPS);
+
+    let mut group = c.benchmark_group("rope_10_knots");
+    group.sample_size(10);
+
+    group.bench_function("float_distance", |b| {
+        b.iter_batched_ref(
+            || vec![Vec2::default(); KNOTS],
+            |knots| {
+                for direction in &directions {
+                    step_float(knots, *direction);
+                }
+                black_box(knots[KNOTS - 1])
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("chebyshev", |b| {
+        b.iter_batched_ref(
+            || Rope::new(KNOTS),
+            |rope| {
+                for direction in &directions {
+                    rope.step(*direction);
+                }
+                black_box(rope.tail())
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_rope);
+criterion_main!(benches);
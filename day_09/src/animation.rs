@@ -0,0 +1,498 @@
+use std::{borrow::Cow, collections::HashSet, fmt, io::Write, ops::Range, str::FromStr};
+
+use anyhow::{bail, Context, Error, Result};
+
+use crate::{follow::FollowRule, Grid, Vec2};
+
+/// A rendered snapshot of the grid, covering the cells from `min` to `max`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    min: Vec2,
+    max: Vec2,
+    cells: Vec<Vec<char>>,
+}
+
+impl Frame {
+    pub(crate) fn blank(min: Vec2, max: Vec2) -> Self {
+        let columns = (max.x - min.x + 1) as usize;
+        let rows = (max.y - min.y + 1) as usize;
+
+        Self {
+            min,
+            max,
+            cells: vec![vec!['.'; columns]; rows],
+        }
+    }
+
+    /// Draws the visited cells, the start and the knots, with knots in front
+    /// of the start and the start in front of visited cells.
+    pub(crate) fn draw<'a>(
+        min: Vec2,
+        max: Vec2,
+        visited: impl IntoIterator<Item = &'a Vec2>,
+        start: Vec2,
+        knots: &[Vec2],
+    ) -> Self {
+        let mut frame = Frame::blank(min, max);
+
+        for pos in visited {
+            frame.mark(*pos, '#');
+        }
+
+        frame.mark(start, 's');
+
+        for (i, knot) in knots.iter().enumerate().rev() {
+            frame.mark(*knot, knot_marker(i));
+        }
+
+        frame
+    }
+
+    /// Sets the marker of a cell. Cells outside of the frame are ignored.
+    pub(crate) fn mark(&mut self, pos: Vec2, ch: char) {
+        if let Some(cell) = self.cell_mut(pos) {
+            *cell = ch;
+        }
+    }
+
+    fn cell_mut(&mut self, pos: Vec2) -> Option<&mut char> {
+        if pos.x < self.min.x || pos.y < self.min.y {
+            return None;
+        }
+
+        self.cells
+            .get_mut((pos.y - self.min.y) as usize)
+            .and_then(|row| row.get_mut((pos.x - self.min.x) as usize))
+    }
+
+    pub fn get(&self, pos: Vec2) -> Option<char> {
+        if pos.x < self.min.x || pos.y < self.min.y {
+            return None;
+        }
+
+        self.cells
+            .get((pos.y - self.min.y) as usize)
+            .and_then(|row| row.get((pos.x - self.min.x) as usize))
+            .copied()
+    }
+
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        (self.min, self.max)
+    }
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.cells.iter().rev() {
+            writeln!(f, "{}", row.iter().collect::<String>())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn knot_marker(i: usize) -> char {
+    if i == 0 {
+        'H'
+    } else {
+        char::from_digit(i as u32, 36).unwrap_or('*')
+    }
+}
+
+/// Grows the bounds `(min, max)` so they contain `pos`.
+pub(crate) fn extend_bounds((min, max): (Vec2, Vec2), pos: Vec2) -> (Vec2, Vec2) {
+    (
+        Vec2 {
+            x: min.x.min(pos.x),
+            y: min.y.min(pos.y),
+        },
+        Vec2 {
+            x: max.x.max(pos.x),
+            y: max.y.max(pos.y),
+        },
+    )
+}
+
+/// A fixed-size window that is centred on the head of the rope.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Viewport {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Viewport {
+    pub(crate) fn around(&self, center: Vec2) -> (Vec2, Vec2) {
+        let min = Vec2 {
+            x: center.x - self.width as i32 / 2,
+            y: center.y - self.height as i32 / 2,
+        };
+        let max = Vec2 {
+            x: min.x + self.width as i32 - 1,
+            y: min.y + self.height as i32 - 1,
+        };
+
+        (min, max)
+    }
+}
+
+impl FromStr for Viewport {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height) = s.split_once('x').context("expected <width>x<height>")?;
+
+        let width = width.parse()?;
+        let height = height.parse()?;
+
+        if width == 0 || height == 0 {
+            bail!("viewport must not be empty");
+        }
+
+        Ok(Self { width, height })
+    }
+}
+
+/// When a new frame is recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Granularity {
+    /// After every single step of the head.
+    Step,
+    /// After every movement line of the input.
+    Movement,
+}
+
+/// Palette used for GIF output, indexed by [`color_index`].
+const PALETTE: [u8; 15] = [
+    0x10, 0x10, 0x18, // empty cell
+    0x60, 0x60, 0x70, // visited by the tail
+    0x30, 0xc0, 0x30, // start
+    0xe0, 0x30, 0x30, // head
+    0xe0, 0xc0, 0x30, // other knots
+];
+
+fn color_index(ch: char) -> u8 {
+    match ch {
+        '.' => 0,
+        '#' => 1,
+        's' => 2,
+        'H' => 3,
+        _ => 4,
+    }
+}
+
+/// What is needed to draw a recorded frame again.
+#[derive(Clone, Debug)]
+struct FrameRecord {
+    /// Number of cells in [`Animation::visited`] that had been visited.
+    visited: usize,
+    /// Range of the frame's knots in [`Animation::knots`].
+    knots: Range<usize>,
+    min: Vec2,
+    max: Vec2,
+}
+
+/// Records the grid after every step or movement. Frames are not stored as
+/// pictures, only the knots and the number of visited cells are kept, and
+/// the pictures are drawn again while writing.
+#[derive(Default)]
+pub struct Animation {
+    viewport: Option<Viewport>,
+    start: Vec2,
+    /// Cells visited by the tail, in the order of their first visit.
+    visited: Vec<Vec2>,
+    /// Bounds of the start and all visited cells.
+    visited_bounds: (Vec2, Vec2),
+    /// Knots of all frames, one frame after another.
+    knots: Vec<Vec2>,
+    frames: Vec<FrameRecord>,
+}
+
+impl Animation {
+    pub fn new(viewport: Option<Viewport>) -> Self {
+        Self {
+            viewport,
+            ..Default::default()
+        }
+    }
+
+    /// Records the current state of `grid`. All frames of an animation have
+    /// to come from the same grid.
+    pub fn record<R: FollowRule>(&mut self, grid: &Grid<R>) {
+        if self.frames.is_empty() {
+            self.start = grid.start;
+            self.visited_bounds = (grid.start, grid.start);
+        }
+
+        for pos in &grid.tail_order[self.visited.len()..] {
+            self.visited_bounds = extend_bounds(self.visited_bounds, *pos);
+            self.visited.push(*pos);
+        }
+
+        let knots = grid.rope.knots();
+
+        let (min, max) = match self.viewport {
+            Some(viewport) => viewport.around(knots[0]),
+            None => knots.iter().fold(self.visited_bounds, |bounds, knot| {
+                extend_bounds(bounds, *knot)
+            }),
+        };
+
+        let first_knot = self.knots.len();
+        self.knots.extend_from_slice(knots);
+
+        self.frames.push(FrameRecord {
+            visited: self.visited.len(),
+            knots: first_knot..self.knots.len(),
+            min,
+            max,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Draws frame `i`, as [`Grid::render`] did when it was recorded.
+    pub fn frame(&self, i: usize) -> Option<Frame> {
+        let record = self.frames.get(i)?;
+
+        Some(Frame::draw(
+            record.min,
+            record.max,
+            &self.visited[..record.visited],
+            self.start,
+            &self.knots[record.knots.clone()],
+        ))
+    }
+
+    /// Writes all frames as plain text, each one preceded by its number.
+    pub fn write_text<W: Write>(&self, mut writer: W) -> Result<()> {
+        for i in 0..self.len() {
+            let frame = self.frame(i).expect("frame index is in range");
+
+            writeln!(writer, "== {i} ==")?;
+            write!(writer, "{frame}")?;
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Encodes all frames as a looping GIF. Every cell becomes a square of
+    /// `scale` pixels, `delay` is given in hundredths of a second. After the
+    /// first frame only the area that changed is encoded.
+    pub fn write_gif<W: Write>(&self, writer: W, scale: u16, delay: u16) -> Result<()> {
+        let Some((min, max)) = self.canvas() else {
+            bail!("no frames recorded");
+        };
+
+        let scale = scale.max(1) as usize;
+        let columns = (max.x - min.x + 1) as usize;
+        let rows = (max.y - min.y + 1) as usize;
+
+        let width: u16 = (columns * scale)
+            .try_into()
+            .context("animation too wide for GIF")?;
+        let height: u16 = (rows * scale)
+            .try_into()
+            .context("animation too high for GIF")?;
+
+        let mut encoder = gif::Encoder::new(writer, width, height, &PALETTE)?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        let mut visited = HashSet::new();
+        let mut previous: Option<&FrameRecord> = None;
+        let mut pixels = Vec::new();
+
+        for record in &self.frames {
+            let new_cells = &self.visited[previous.map_or(0, |p| p.visited)..record.visited];
+            visited.extend(new_cells.iter().copied());
+
+            let knots = &self.knots[record.knots.clone()];
+
+            let (dirty_min, dirty_max) = match previous {
+                None => (min, max),
+                Some(previous) => {
+                    let mut changed = knots
+                        .iter()
+                        .chain(&self.knots[previous.knots.clone()])
+                        .chain(new_cells)
+                        .copied()
+                        .collect::<Vec<_>>();
+
+                    if (previous.min, previous.max) != (record.min, record.max) {
+                        changed.extend([previous.min, previous.max, record.min, record.max]);
+                    }
+
+                    let first = changed[0];
+                    changed.into_iter().fold((first, first), extend_bounds)
+                }
+            };
+
+            let dirty_columns = (dirty_max.x - dirty_min.x + 1) as usize;
+            let dirty_rows = (dirty_max.y - dirty_min.y + 1) as usize;
+
+            pixels.clear();
+            pixels.resize(dirty_columns * scale * dirty_rows * scale, 0);
+
+            for row in 0..dirty_rows {
+                // GIF rows go top-down, the grid's y axis points up.
+                let y = dirty_max.y - row as i32;
+
+                for column in 0..dirty_columns {
+                    let pos = Vec2 {
+                        x: dirty_min.x + column as i32,
+                        y,
+                    };
+
+                    let inside = record.min.x <= pos.x
+                        && pos.x <= record.max.x
+                        && record.min.y <= pos.y
+                        && pos.y <= record.max.y;
+
+                    let marker = if inside {
+                        self.marker(pos, &visited, knots)
+                    } else {
+                        '.'
+                    };
+
+                    for dy in 0..scale {
+                        let start = (row * scale + dy) * dirty_columns * scale + column * scale;
+                        pixels[start..start + scale].fill(color_index(marker));
+                    }
+                }
+            }
+
+            let gif_frame = gif::Frame {
+                left: ((dirty_min.x - min.x) as usize * scale) as u16,
+                top: ((max.y - dirty_max.y) as usize * scale) as u16,
+                width: (dirty_columns * scale) as u16,
+                height: (dirty_rows * scale) as u16,
+                delay,
+                dispose: gif::DisposalMethod::Keep,
+                buffer: Cow::Borrowed(&pixels),
+                ..Default::default()
+            };
+            encoder.write_frame(&gif_frame)?;
+
+            previous = Some(record);
+        }
+
+        Ok(())
+    }
+
+    /// The marker [`Frame::draw`] puts at `pos`.
+    fn marker(&self, pos: Vec2, visited: &HashSet<Vec2>, knots: &[Vec2]) -> char {
+        if let Some(i) = knots.iter().position(|knot| *knot == pos) {
+            knot_marker(i)
+        } else if pos == self.start {
+            's'
+        } else if visited.contains(&pos) {
+            '#'
+        } else {
+            '.'
+        }
+    }
+
+    /// Smallest area containing all frames, used as the GIF canvas.
+    fn canvas(&self) -> Option<(Vec2, Vec2)> {
+        self.frames
+            .iter()
+            .map(|record| (record.min, record.max))
+            .reduce(|bounds, (min, max)| extend_bounds(extend_bounds(bounds, min), max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records a small rope after every movement and every step.
+    fn record(viewport: Option<Viewport>, every_step: bool) -> (Animation, Vec<Frame>) {
+        let mut grid = Grid::new(2);
+        let mut animation = Animation::new(viewport);
+        let mut expected = Vec::new();
+
+        animation.record(&grid);
+        expected.push(grid.render(viewport));
+
+        for movement in ["R 2", "U 1", "UL 2"] {
+            let movement = movement.parse().unwrap();
+
+            if every_step {
+                grid.apply_movement_with(movement, |grid| {
+                    animation.record(grid);
+                    expected.push(grid.render(viewport));
+                });
+            } else {
+                grid.apply_movement(movement);
+                animation.record(&grid);
+                expected.push(grid.render(viewport));
+            }
+        }
+
+        (animation, expected)
+    }
+
+    #[test]
+    fn test_frames_match_render() {
+        for viewport in [
+            None,
+            Some(Viewport {
+                width: 3,
+                height: 2,
+            }),
+        ] {
+            for every_step in [false, true] {
+                let (animation, expected) = record(viewport, every_step);
+
+                let frames: Vec<Frame> = (0..animation.len())
+                    .map(|i| animation.frame(i).unwrap())
+                    .collect();
+                assert_eq!(frames, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_write_text() {
+        let (animation, _) = record(None, false);
+
+        let mut text = Vec::new();
+        animation.write_text(&mut text).unwrap();
+
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            "== 0 ==\nH\n\n\
+             == 1 ==\ns1H\n\n\
+             == 2 ==\n..H\ns1.\n\n\
+             == 3 ==\nH.\n1.\n.#\ns#\n\n"
+        );
+    }
+
+    #[test]
+    fn test_write_gif() {
+        let (animation, _) = record(None, true);
+
+        let mut bytes = Vec::new();
+        animation.write_gif(&mut bytes, 4, 5).unwrap();
+
+        let mut decoder = gif::DecodeOptions::new().read_info(&bytes[..]).unwrap();
+        // The frames span three columns and four rows.
+        assert_eq!((decoder.width(), decoder.height()), (3 * 4, 4 * 4));
+
+        let mut sizes = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert!(frame.left + frame.width <= 12 && frame.top + frame.height <= 16);
+            sizes.push((frame.width, frame.height));
+        }
+
+        assert_eq!(sizes.len(), animation.len());
+        assert_eq!(sizes[0], (12, 16));
+        assert!(sizes[1..].iter().any(|size| *size != (12, 16)));
+    }
+}
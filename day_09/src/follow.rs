@@ -0,0 +1,166 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Error};
+
+use crate::Vec2;
+
+/// Decides how a knot reacts to the knot in front of it.
+pub trait FollowRule {
+    /// Returns the new position of `follower` after `leader` has moved.
+    fn follow(&self, leader: Vec2, follower: Vec2) -> Vec2;
+
+    /// Whether a follower that has caught up stays put while its leader
+    /// does not move, i.e. `follow(a, follow(a, b)) == follow(a, b)` for
+    /// every position a rope can reach.
+    ///
+    /// [`Rope::step`](crate::Rope::step) stops moving knots at the first
+    /// one that stands still, which is only correct for such rules.
+    fn is_idempotent(&self) -> bool {
+        false
+    }
+}
+
+impl<R: FollowRule + ?Sized> FollowRule for Box<R> {
+    fn follow(&self, leader: Vec2, follower: Vec2) -> Vec2 {
+        (**self).follow(leader, follower)
+    }
+
+    fn is_idempotent(&self) -> bool {
+        (**self).is_idempotent()
+    }
+}
+
+/// The rule from the puzzle: once the knots are no longer touching, the
+/// follower moves one step towards the leader, diagonally if needed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PuzzleRule;
+
+impl FollowRule for PuzzleRule {
+    fn follow(&self, leader: Vec2, follower: Vec2) -> Vec2 {
+        if leader.chebyshev(follower) < 2 {
+            follower
+        } else {
+            follower + (leader - follower).cap()
+        }
+    }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+}
+
+/// Like [`PuzzleRule`], but the rope may stretch up to `max_distance`
+/// cells before the follower starts to move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Slack {
+    pub max_distance: i32,
+}
+
+impl FollowRule for Slack {
+    fn follow(&self, leader: Vec2, follower: Vec2) -> Vec2 {
+        if leader.chebyshev(follower) <= self.max_distance {
+            follower
+        } else {
+            follower + (leader - follower).cap()
+        }
+    }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+}
+
+/// The follower never moves diagonally, it closes the larger gap first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CardinalOnly;
+
+impl FollowRule for CardinalOnly {
+    fn follow(&self, leader: Vec2, follower: Vec2) -> Vec2 {
+        if leader.chebyshev(follower) < 2 {
+            return follower;
+        }
+
+        let diff = leader - follower;
+
+        if diff.x.abs() >= diff.y.abs() {
+            follower
+                + Vec2 {
+                    x: diff.x.signum(),
+                    y: 0,
+                }
+        } else {
+            follower
+                + Vec2 {
+                    x: 0,
+                    y: diff.y.signum(),
+                }
+        }
+    }
+}
+
+/// The follower snaps back next to the leader in a single move, no matter
+/// how far away it is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Elastic;
+
+impl FollowRule for Elastic {
+    fn follow(&self, leader: Vec2, follower: Vec2) -> Vec2 {
+        if leader.chebyshev(follower) < 2 {
+            follower
+        } else {
+            leader - (leader - follower).cap()
+        }
+    }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+}
+
+impl FromStr for Box<dyn FollowRule> {
+    type Err = Error;
+
+    /// Parses `puzzle`, `slack:<n>`, `cardinal` or `elastic`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.split_once(':') {
+            None if s == "puzzle" => Box::new(PuzzleRule),
+            None if s == "cardinal" => Box::new(CardinalOnly),
+            None if s == "elastic" => Box::new(Elastic),
+            Some(("slack", distance)) => {
+                let max_distance = distance.parse().context("invalid slack distance")?;
+
+                if max_distance < 1 {
+                    bail!("slack distance must be at least 1");
+                }
+
+                Box::new(Slack { max_distance })
+            }
+            _ => bail!("invalid follow rule {s}"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: i32, y: i32) -> Vec2 {
+        Vec2 { x, y }
+    }
+
+    #[test]
+    fn test_rules() {
+        assert_eq!(PuzzleRule.follow(v(2, 1), v(0, 0)), v(1, 1));
+        assert_eq!(PuzzleRule.follow(v(1, 1), v(0, 0)), v(0, 0));
+
+        let slack = Slack { max_distance: 2 };
+        assert_eq!(slack.follow(v(2, 1), v(0, 0)), v(0, 0));
+        assert_eq!(slack.follow(v(3, 1), v(0, 0)), v(1, 1));
+
+        assert_eq!(CardinalOnly.follow(v(2, 1), v(0, 0)), v(1, 0));
+        assert_eq!(CardinalOnly.follow(v(1, -2), v(0, 0)), v(0, -1));
+
+        assert_eq!(Elastic.follow(v(5, 3), v(0, 0)), v(4, 2));
+        assert_eq!(Elastic.follow(v(-4, 0), v(0, 0)), v(-3, 0));
+    }
+}
@@ -0,0 +1,390 @@
+use std::{
+    collections::HashSet,
+    ops::{Add, Sub},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context};
+
+pub mod animation;
+pub mod follow;
+
+use animation::{Frame, Viewport};
+use follow::{FollowRule, PuzzleRule};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    pub fn offset(&self) -> Vec2 {
+        match self {
+            Direction::Up => Vec2 { x: 0, y: 1 },
+            Direction::Down => Vec2 { x: 0, y: -1 },
+            Direction::Left => Vec2 { x: -1, y: 0 },
+            Direction::Right => Vec2 { x: 1, y: 0 },
+            Direction::UpLeft => Vec2 { x: -1, y: 1 },
+            Direction::UpRight => Vec2 { x: 1, y: 1 },
+            Direction::DownLeft => Vec2 { x: -1, y: -1 },
+            Direction::DownRight => Vec2 { x: 1, y: -1 },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Movement {
+    pub direction: Direction,
+    pub number_of_steps: u32,
+}
+
+impl FromStr for Movement {
+    type Err = anyhow::Error;
+
+    /// Parses `<direction> <steps>`, where the direction is one of `U`,
+    /// `D`, `L`, `R`, `UL`, `UR`, `DL` or `DR`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut splitted = s.split_whitespace();
+
+        let direction = match splitted.next().context("missing direction")? {
+            "U" => Direction::Up,
+            "D" => Direction::Down,
+            "L" => Direction::Left,
+            "R" => Direction::Right,
+            "UL" => Direction::UpLeft,
+            "UR" => Direction::UpRight,
+            "DL" => Direction::DownLeft,
+            "DR" => Direction::DownRight,
+            direction => bail!("invalid direction {direction}"),
+        };
+
+        let steps = splitted
+            .next()
+            .context("missing number of steps")?
+            .parse()
+            .context("invalid number of steps")?;
+
+        if let Some(garbage) = splitted.next() {
+            bail!("unexpected {garbage:?} after the number of steps");
+        }
+
+        Ok(Self {
+            direction,
+            number_of_steps: steps,
+        })
+    }
+}
+
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Vec2 {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Add for Vec2 {
+    type Output = Vec2;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Vec2 {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+impl Sub for Vec2 {
+    type Output = Vec2;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Vec2 {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+
+impl Vec2 {
+    pub fn cap(&self) -> Self {
+        Self {
+            x: cap(self.x),
+            y: cap(self.y),
+        }
+    }
+
+    /// Chessboard distance, i.e. the number of king moves between two cells.
+    pub fn chebyshev(&self, other: Self) -> i32 {
+        (self.x - other.x).abs().max((self.y - other.y).abs())
+    }
+}
+
+fn cap(number: i32) -> i32 {
+    match number.cmp(&0) {
+        std::cmp::Ordering::Less => -1,
+        std::cmp::Ordering::Equal => 0,
+        std::cmp::Ordering::Greater => 1,
+    }
+}
+
+/// A rope of knots where every knot follows the one in front of it.
+#[derive(Clone, Debug)]
+pub struct Rope<R = PuzzleRule> {
+    knots: Vec<Vec2>,
+    rule: R,
+}
+
+impl Rope {
+    pub fn new(knots: usize) -> Self {
+        Self::with_rule(knots, PuzzleRule)
+    }
+}
+
+impl<R: FollowRule> Rope<R> {
+    pub fn with_rule(knots: usize, rule: R) -> Self {
+        assert!(knots > 0, "a rope needs at least one knot");
+
+        Self {
+            knots: vec![Default::default(); knots],
+            rule,
+        }
+    }
+
+    pub fn knots(&self) -> &[Vec2] {
+        &self.knots
+    }
+
+    pub fn head(&self) -> Vec2 {
+        self.knots[0]
+    }
+
+    pub fn tail(&self) -> Vec2 {
+        self.knots[self.knots.len() - 1]
+    }
+
+    /// Moves the head one cell and lets the remaining knots follow.
+    pub fn step(&mut self, direction: Direction) {
+        self.knots[0] = self.knots[0] + direction.offset();
+
+        for i in 1..self.knots.len() {
+            let next = self.rule.follow(self.knots[i - 1], self.knots[i]);
+
+            if next == self.knots[i] && self.rule.is_idempotent() {
+                // Knots further down the rope can only move if this one did.
+                break;
+            }
+
+            self.knots[i] = next;
+        }
+    }
+}
+
+/// Which knots should have their visited cells recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tracking {
+    Tail,
+    AllKnots,
+}
+
+pub struct Grid<R = PuzzleRule> {
+    /// One visit set per tracked knot. With [`Tracking::Tail`] this holds
+    /// only the set of the last knot.
+    visited: Vec<HashSet<Vec2>>,
+    /// Cells visited by the tail, in the order of their first visit. Lets
+    /// an animation pick up the new cells without scanning the visit set.
+    tail_order: Vec<Vec2>,
+    tracking: Tracking,
+    rope: Rope<R>,
+    start: Vec2,
+}
+
+impl Grid {
+    pub fn new(knots: usize) -> Self {
+        Self::with_tracking(knots, Tracking::Tail)
+    }
+
+    pub fn with_tracking(knots: usize, tracking: Tracking) -> Self {
+        Grid::with_rule(knots, tracking, PuzzleRule)
+    }
+}
+
+impl<R: FollowRule> Grid<R> {
+    pub fn with_rule(knots: usize, tracking: Tracking, rule: R) -> Self {
+        let start = Vec2::default();
+
+        let sets = match tracking {
+            Tracking::Tail => 1,
+            Tracking::AllKnots => knots,
+        };
+
+        Self {
+            visited: vec![HashSet::from([start]); sets],
+            tail_order: vec![start],
+            tracking,
+            rope: Rope::with_rule(knots, rule),
+            start,
+        }
+    }
+
+    pub fn print(&self) {
+        print!("{}", self.render(None));
+    }
+
+    /// Draws the current state. Without a viewport the frame covers every
+    /// visited cell and knot, otherwise it is centred on the head.
+    pub fn render(&self, viewport: Option<Viewport>) -> Frame {
+        let (min, max) = match viewport {
+            Some(viewport) => viewport.around(self.rope.head()),
+            None => self.bounds(),
+        };
+
+        Frame::draw(min, max, self.tail_visited(), self.start, self.rope.knots())
+    }
+
+    fn bounds(&self) -> (Vec2, Vec2) {
+        self.tail_visited()
+            .chain(self.rope.knots().iter())
+            .fold((self.start, self.start), |bounds, pos| {
+                animation::extend_bounds(bounds, *pos)
+            })
+    }
+
+    fn tail_visited(&self) -> impl Iterator<Item = &Vec2> {
+        self.visited[self.visited.len() - 1].iter()
+    }
+
+    pub fn apply_movement(&mut self, movement: Movement) {
+        self.apply_movement_with(movement, |_| {});
+    }
+
+    /// Like [`Grid::apply_movement`], but calls `on_step` after every step.
+    pub fn apply_movement_with<F>(&mut self, movement: Movement, mut on_step: F)
+    where
+        F: FnMut(&Grid<R>),
+    {
+        for _ in 0..movement.number_of_steps {
+            self.rope.step(movement.direction);
+
+            let new_tail_cell = match self.tracking {
+                Tracking::Tail => self.visited[0].insert(self.rope.tail()),
+                Tracking::AllKnots => {
+                    let mut inserted = false;
+                    for (visited, knot) in self.visited.iter_mut().zip(self.rope.knots()) {
+                        inserted = visited.insert(*knot);
+                    }
+                    inserted
+                }
+            };
+
+            if new_tail_cell {
+                self.tail_order.push(self.rope.tail());
+            }
+
+            on_step(self);
+        }
+    }
+
+    pub fn count_visited(&self) -> usize {
+        self.visited[self.visited.len() - 1].len()
+    }
+
+    /// Number of cells visited by every knot, indexed like the rope.
+    ///
+    /// Knot `i` of a rope behaves exactly like the tail of a rope with
+    /// `i + 1` knots, so a single run answers all shorter ropes as well.
+    /// Returns `None` unless the grid tracks [`Tracking::AllKnots`].
+    pub fn count_visited_per_knot(&self) -> Option<Vec<usize>> {
+        match self.tracking {
+            Tracking::Tail => None,
+            Tracking::AllKnots => Some(self.visited.iter().map(HashSet::len).collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_movements() {
+        assert_eq!(
+            "UR 2".parse::<Movement>().unwrap(),
+            Movement {
+                direction: Direction::UpRight,
+                number_of_steps: 2,
+            }
+        );
+
+        for movement in ["R 4 junk", "X 1", "R", "R -1", "", "DR 2 3"] {
+            assert!(movement.parse::<Movement>().is_err(), "{movement}");
+        }
+    }
+
+    #[test]
+    fn test_diagonal_head_moves() {
+        let mut grid = Grid::new(2);
+        grid.apply_movement("UR 3".parse().unwrap());
+        grid.apply_movement("DL 1".parse().unwrap());
+
+        assert_eq!(grid.rope.head(), Vec2 { x: 2, y: 2 });
+        assert_eq!(grid.rope.tail(), Vec2 { x: 2, y: 2 });
+        assert_eq!(grid.count_visited(), 3);
+    }
+
+    /// Hides whether the wrapped rule is idempotent, so every knot is
+    /// followed on every step.
+    struct FullPropagation<'a>(&'a dyn FollowRule);
+
+    impl FollowRule for FullPropagation<'_> {
+        fn follow(&self, leader: Vec2, follower: Vec2) -> Vec2 {
+            self.0.follow(leader, follower)
+        }
+    }
+
+    #[test]
+    fn test_early_break_matches_full_propagation() {
+        fn run<R: FollowRule>(rule: R) -> Vec<Vec2> {
+            let mut rope = Rope::with_rule(10, rule);
+            for movement in ["R 5", "U 8", "UL 4", "L 8", "D 3", "DR 6", "R 17", "D 10"] {
+                let movement: Movement = movement.parse().unwrap();
+                for _ in 0..movement.number_of_steps {
+                    rope.step(movement.direction);
+                }
+            }
+            rope.knots().to_vec()
+        }
+
+        let rules: [Box<dyn FollowRule>; 5] = [
+            Box::new(follow::PuzzleRule),
+            Box::new(follow::Slack { max_distance: 3 }),
+            Box::new(follow::CardinalOnly),
+            Box::new(follow::Elastic),
+            "slack:2".parse().unwrap(),
+        ];
+
+        for rule in rules {
+            let idempotent = rule.is_idempotent();
+            let expected = run(FullPropagation(rule.as_ref()));
+            assert_eq!(run(rule), expected, "idempotent: {idempotent}");
+        }
+    }
+
+    #[test]
+    fn test_all_knots_match_shorter_ropes() {
+        let moves = ["R 5", "U 8", "L 8", "D 3", "R 17", "D 10", "L 25", "U 20"];
+
+        let mut grid = Grid::with_tracking(10, Tracking::AllKnots);
+        for movement in moves {
+            grid.apply_movement(movement.parse().unwrap());
+        }
+
+        let counts = grid.count_visited_per_knot().unwrap();
+        assert_eq!(counts[1], 88);
+        assert_eq!(counts[9], 36);
+        assert_eq!(counts[9], grid.count_visited());
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+
+use day_09::{animation::Viewport, Direction, Grid, Movement, Vec2};
+use proptest::prelude::*;
+
+type Cell = (i32, i32);
+
+/// Straightforward simulation that keeps every knot as a plain tuple and
+/// checks the follow rule with the euclidean distance, like the original
+/// implementation did.
+fn reference(movements: &[Movement], knots: usize) -> (HashSet<Cell>, Vec<Cell>) {
+    let mut rope: Vec<Cell> = vec![(0, 0); knots];
+    let mut visited = HashSet::from([(0, 0)]);
+
+    for movement in movements {
+        let (dx, dy) = match movement.direction {
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::UpLeft => (-1, 1),
+            Direction::UpRight => (1, 1),
+            Direction::DownLeft => (-1, -1),
+            Direction::DownRight => (1, -1),
+        };
+
+        for _ in 0..movement.number_of_steps {
+            rope[0] = (rope[0].0 + dx, rope[0].1 + dy);
+
+            for i in 1..knots {
+                let (hx, hy) = rope[i - 1];
+                let (tx, ty) = rope[i];
+
+                if (((hx - tx).pow(2) + (hy - ty).pow(2)) as f32).sqrt() >= 2.0 {
+                    rope[i] = (tx + (hx - tx).signum(), ty + (hy - ty).signum());
+                }
+            }
+
+            visited.insert(rope[knots - 1]);
+        }
+    }
+
+    (visited, rope)
+}
+
+/// The marker the renderer is expected to draw at `pos`.
+fn expected_marker(pos: Cell, visited: &HashSet<Cell>, rope: &[Cell]) -> char {
+    if let Some(i) = rope.iter().position(|knot| *knot == pos) {
+        if i == 0 {
+            'H'
+        } else {
+            char::from_digit(i as u32, 36).unwrap_or('*')
+        }
+    } else if pos == (0, 0) {
+        's'
+    } else if visited.contains(&pos) {
+        '#'
+    } else {
+        '.'
+    }
+}
+
+fn movement() -> impl Strategy<Value = Movement> {
+    (
+        prop_oneof![
+            Just(Direction::Up),
+            Just(Direction::Down),
+            Just(Direction::Left),
+            Just(Direction::Right),
+            Just(Direction::UpLeft),
+            Just(Direction::UpRight),
+            Just(Direction::DownLeft),
+            Just(Direction::DownRight),
+        ],
+        1u32..12,
+    )
+        .prop_map(|(direction, number_of_steps)| Movement {
+            direction,
+            number_of_steps,
+        })
+}
+
+fn simulate(movements: &[Movement], knots: usize) -> Grid {
+    let mut grid = Grid::new(knots);
+    for movement in movements {
+        grid.apply_movement(*movement);
+    }
+    grid
+}
+
+proptest! {
+    #[test]
+    fn visited_count_matches_reference(
+        movements in prop::collection::vec(movement(), 0..60),
+        knots in 1usize..12,
+    ) {
+        let grid = simulate(&movements, knots);
+        let (visited, _) = reference(&movements, knots);
+
+        prop_assert_eq!(grid.count_visited(), visited.len());
+    }
+
+    #[test]
+    fn rendered_grid_matches_reference(
+        movements in prop::collection::vec(movement(), 0..60),
+        knots in 1usize..12,
+    ) {
+        let grid = simulate(&movements, knots);
+        let (visited, rope) = reference(&movements, knots);
+
+        let frame = grid.render(None);
+        let (min, max) = frame.bounds();
+
+        for pos in visited.iter().chain(rope.iter()).chain(std::iter::once(&(0, 0))) {
+            prop_assert!(min.x <= pos.0 && pos.0 <= max.x && min.y <= pos.1 && pos.1 <= max.y);
+        }
+
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                prop_assert_eq!(
+                    frame.get(Vec2 { x, y }),
+                    Some(expected_marker((x, y), &visited, &rope)),
+                    "at ({}, {})", x, y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn viewport_is_window_of_full_frame(
+        movements in prop::collection::vec(movement(), 0..60),
+        knots in 1usize..12,
+        width in 1u32..30,
+        height in 1u32..30,
+    ) {
+        let grid = simulate(&movements, knots);
+        let (visited, rope) = reference(&movements, knots);
+
+        let frame = grid.render(Some(Viewport { width, height }));
+        let (min, max) = frame.bounds();
+
+        prop_assert_eq!((max.x - min.x + 1) as u32, width);
+        prop_assert_eq!((max.y - min.y + 1) as u32, height);
+        prop_assert_eq!(frame.get(Vec2 { x: rope[0].0, y: rope[0].1 }), Some('H'));
+
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                prop_assert_eq!(
+                    frame.get(Vec2 { x, y }),
+                    Some(expected_marker((x, y), &visited, &rope)),
+                    "at ({}, {})", x, y
+                );
+            }
+        }
+    }
+}
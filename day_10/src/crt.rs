@@ -0,0 +1,180 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Error};
+
+pub const WIDTH: usize = 40;
+pub const HEIGHT: usize = 6;
+
+/// The pixels covered by the sprite, centred on the X register.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sprite {
+    mask: Vec<bool>,
+}
+
+impl Default for Sprite {
+    fn default() -> Self {
+        Self::solid(3)
+    }
+}
+
+impl Sprite {
+    /// A sprite of `width` lit pixels. The width must be odd.
+    pub fn solid(width: usize) -> Self {
+        assert!(!width.is_multiple_of(2), "sprite width must be odd");
+
+        Self {
+            mask: vec![true; width],
+        }
+    }
+
+    fn covers(&self, sprite_x: i32, col: i32) -> bool {
+        let offset = col - sprite_x + (self.mask.len() / 2) as i32;
+
+        usize::try_from(offset)
+            .ok()
+            .and_then(|offset| self.mask.get(offset))
+            .copied()
+            .unwrap_or(false)
+    }
+}
+
+impl FromStr for Sprite {
+    type Err = Error;
+
+    /// Parses either a width like `5` or a pattern like `#.#.#`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mask: Vec<bool> = match s.parse::<usize>() {
+            Ok(width) => vec![true; width],
+            Err(_) => s
+                .chars()
+                .map(|ch| match ch {
+                    '#' => Ok(true),
+                    '.' => Ok(false),
+                    _ => bail!("invalid sprite pixel {ch}"),
+                })
+                .collect::<Result<_, _>>()?,
+        };
+
+        if mask.len().is_multiple_of(2) {
+            bail!("sprite width must be odd to be centred");
+        }
+
+        Ok(Self { mask })
+    }
+}
+
+/// The screen of the device. The CPU draws one pixel per cycle, row by row.
+#[derive(Debug, Clone)]
+pub struct Crt {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>,
+    sprite: Sprite,
+}
+
+/// Screens are equal if they show the same picture, the sprite that drew
+/// it does not matter.
+impl PartialEq for Crt {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width && self.height == other.height && self.pixels == other.pixels
+    }
+}
+
+impl Eq for Crt {}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Self::new(WIDTH, HEIGHT)
+    }
+}
+
+impl Crt {
+    pub fn new(width: usize, height: usize) -> Self {
+        assert!(width > 0 && height > 0, "screen must not be empty");
+
+        Self {
+            width,
+            height,
+            pixels: vec![false; width * height],
+            sprite: Sprite::default(),
+        }
+    }
+
+    pub fn with_sprite(self, sprite: Sprite) -> Self {
+        Self { sprite, ..self }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Draws the pixel for `cycle` (starting at 1) with the sprite centred
+    /// on column `sprite_x`. After the last pixel the beam wraps around.
+    pub fn draw(&mut self, cycle: i32, sprite_x: i32) {
+        let position = (cycle - 1) as usize % self.pixels.len();
+        let col = (position % self.width) as i32;
+
+        self.pixels[position] = self.sprite.covers(sprite_x, col);
+    }
+
+    pub fn render(&self) -> String {
+        self.render_until(self.pixels.len() as i32 + 1)
+    }
+
+    /// Renders the screen as it looks before `cycle` is drawn. Pixels that
+    /// have not been reached by the beam yet are left blank.
+    pub fn render_until(&self, cycle: i32) -> String {
+        let drawn = (cycle - 1).max(0) as usize;
+        let mut screen = String::with_capacity((self.width + 1) * self.height);
+
+        for (y, row) in self.pixels.chunks(self.width).enumerate() {
+            screen.extend(row.iter().enumerate().map(|(x, lit)| {
+                if y * self.width + x >= drawn {
+                    ' '
+                } else if *lit {
+                    '#'
+                } else {
+                    '.'
+                }
+            }));
+            screen.push('\n');
+        }
+
+        screen
+    }
+}
+
+impl FromStr for Crt {
+    type Err = Error;
+
+    /// Parses a rendered picture, so screens can be compared in tests.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows: Vec<&str> = s.lines().collect();
+        let width = rows.first().map(|row| row.len()).context("empty picture")?;
+
+        let mut pixels = Vec::with_capacity(width * rows.len());
+
+        for (i, row) in rows.iter().enumerate() {
+            if row.len() != width {
+                bail!("row {} has {} pixels, expected {width}", i + 1, row.len());
+            }
+
+            for ch in row.chars() {
+                pixels.push(match ch {
+                    '#' => true,
+                    '.' => false,
+                    _ => bail!("invalid pixel {ch} in row {}", i + 1),
+                });
+            }
+        }
+
+        Ok(Self {
+            pixels,
+            ..Self::new(width, rows.len())
+        })
+    }
+}
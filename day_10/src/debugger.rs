@@ -0,0 +1,326 @@
+use std::io::{BufRead, Write};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{instruction::Program, Cpu, DEFAULT_CYCLE_LIMIT};
+
+/// Conditions that stop `continue`.
+#[derive(Debug, Default)]
+struct Breakpoints {
+    cycles: Vec<i32>,
+    x_values: Vec<i32>,
+}
+
+impl Breakpoints {
+    fn hit(&self, cpu: &Cpu) -> Option<String> {
+        if self.cycles.contains(&cpu.cycle) {
+            Some(format!("breakpoint at cycle {}", cpu.cycle))
+        } else if self.x_values.contains(&cpu.x()) {
+            Some(format!("breakpoint on x == {}", cpu.x()))
+        } else {
+            None
+        }
+    }
+}
+
+enum Command {
+    Step(u32),
+    Next(u32),
+    Continue,
+    BreakCycle(i32),
+    BreakX(i32),
+    Clear,
+    Registers,
+    Screen,
+    Help,
+    Quit,
+}
+
+fn parse_command(line: &str) -> Result<Command> {
+    let mut parts = line.split_whitespace();
+    let command = parts.next().unwrap_or("step");
+    let argument = parts.next();
+
+    let count = || -> Result<u32> {
+        argument
+            .map(str::parse)
+            .transpose()
+            .context("invalid count")
+            .map(|count| count.unwrap_or(1))
+    };
+    let value = || -> Result<i32> {
+        argument
+            .context("missing value")?
+            .parse()
+            .context("invalid value")
+    };
+
+    let command = match command {
+        "s" | "step" => Command::Step(count()?),
+        "n" | "next" => Command::Next(count()?),
+        "c" | "continue" => Command::Continue,
+        "b" | "break" => Command::BreakCycle(value()?),
+        "w" | "watch" => Command::BreakX(value()?),
+        "clear" => Command::Clear,
+        "r" | "regs" => Command::Registers,
+        "screen" => Command::Screen,
+        "h" | "help" => Command::Help,
+        "q" | "quit" => Command::Quit,
+        _ => bail!("unknown command {command}, try help"),
+    };
+
+    if parts.next().is_some() {
+        bail!("too many arguments");
+    }
+
+    Ok(command)
+}
+
+const HELP: &str = "\
+step [n]     execute n cycles (default 1), also an empty line
+next [n]     execute n instructions, finishing the current one first
+continue     run until a breakpoint, the end or at most 1000000 cycles
+break <n>    stop before cycle n
+watch <v>    stop when x equals v
+clear        remove all breakpoints
+regs         show the registers
+screen       show the screen drawn so far
+quit         leave the debugger";
+
+/// Why a command that runs the program handed control back.
+enum Outcome {
+    Paused,
+    Stopped(String),
+    Finished,
+}
+
+/// Calls `step` up to `count` times, until the program finishes.
+fn repeat(count: u32, mut step: impl FnMut() -> Result<bool>) -> Result<Outcome> {
+    for _ in 0..count {
+        if !step()? {
+            return Ok(Outcome::Finished);
+        }
+    }
+
+    Ok(Outcome::Paused)
+}
+
+/// Runs until a breakpoint is hit, the program finishes, or the cycle
+/// limit has been reached.
+fn run_to_breakpoint(
+    cpu: &mut Cpu,
+    program: &Program,
+    breakpoints: &Breakpoints,
+) -> Result<Outcome> {
+    let mut cycles = 0;
+
+    loop {
+        if !cpu.step_cycle(program)? {
+            return Ok(Outcome::Finished);
+        }
+
+        cycles += 1;
+
+        if let Some(reason) = breakpoints.hit(cpu) {
+            return Ok(Outcome::Stopped(reason));
+        }
+
+        if cycles == DEFAULT_CYCLE_LIMIT {
+            return Ok(Outcome::Stopped(format!("stopped after {cycles} cycles")));
+        }
+    }
+}
+
+fn print_status<W: Write>(cpu: &Cpu, program: &Program, output: &mut W) -> Result<()> {
+    let [x, y, z] = cpu.registers;
+
+    match cpu.current_instruction(program) {
+        Some(instruction) => writeln!(
+            output,
+            "cycle {} pc {} [{instruction} {}/{}] x={x} y={y} z={z}",
+            cpu.cycle,
+            cpu.pc,
+            cpu.phase + 1,
+            instruction.cycles()
+        )?,
+        None => writeln!(
+            output,
+            "cycle {} pc {} [halted] x={x} y={y} z={z}",
+            cpu.cycle, cpu.pc
+        )?,
+    }
+
+    Ok(())
+}
+
+/// Runs `program` under control of the commands read from `input`. The
+/// debugger stops at the end of the input or on `quit`.
+pub fn run<R: BufRead, W: Write>(
+    cpu: &mut Cpu,
+    program: &Program,
+    input: R,
+    mut output: W,
+) -> Result<()> {
+    let mut breakpoints = Breakpoints::default();
+
+    print_status(cpu, program, &mut output)?;
+
+    for line in input.lines() {
+        let command = match parse_command(&line?) {
+            Ok(command) => command,
+            Err(error) => {
+                writeln!(output, "error: {error}")?;
+                continue;
+            }
+        };
+
+        let outcome = match command {
+            Command::Step(count) => repeat(count, || cpu.step_cycle(program)),
+            Command::Next(count) => repeat(count, || cpu.step_instruction(program)),
+            Command::Continue => run_to_breakpoint(cpu, program, &breakpoints),
+            Command::BreakCycle(cycle) => {
+                breakpoints.cycles.push(cycle);
+                continue;
+            }
+            Command::BreakX(value) => {
+                breakpoints.x_values.push(value);
+                continue;
+            }
+            Command::Clear => {
+                breakpoints = Breakpoints::default();
+                continue;
+            }
+            Command::Registers => Ok(Outcome::Paused),
+            Command::Screen => {
+                write!(output, "{}", cpu.crt.render_until(cpu.cycle))?;
+                continue;
+            }
+            Command::Help => {
+                writeln!(output, "{HELP}")?;
+                continue;
+            }
+            Command::Quit => break,
+        };
+
+        // Errors of the program leave the CPU as it was before the failing
+        // cycle, so the session goes on and the state can be inspected.
+        match outcome {
+            Ok(Outcome::Paused) => {}
+            Ok(Outcome::Stopped(reason)) => writeln!(output, "{reason}")?,
+            Ok(Outcome::Finished) => writeln!(output, "program finished")?,
+            Err(error) => writeln!(output, "error: {error:#}")?,
+        }
+
+        print_status(cpu, program, &mut output)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    fn debug(script: &str) -> String {
+        let program = "noop\naddx 3\naddx -5\n".parse().unwrap();
+        let mut cpu = Cpu::with_config(Config::default());
+
+        let mut output = Vec::new();
+        run(&mut cpu, &program, script.as_bytes(), &mut output).unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_stepping() {
+        let output = debug("step\nnext\nstep\nregs\nnext 5\n");
+
+        assert_eq!(
+            output,
+            "\
+cycle 1 pc 0 [noop 1/1] x=1 y=0 z=0
+cycle 2 pc 1 [addx 3 1/2] x=1 y=0 z=0
+cycle 4 pc 2 [addx -5 1/2] x=4 y=0 z=0
+cycle 5 pc 2 [addx -5 2/2] x=4 y=0 z=0
+cycle 5 pc 2 [addx -5 2/2] x=4 y=0 z=0
+program finished
+cycle 6 pc 3 [halted] x=-1 y=0 z=0
+"
+        );
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let output = debug("watch 4\nbreak 5\ncontinue\ncontinue\nclear\nc\n");
+
+        assert_eq!(
+            output,
+            "\
+cycle 1 pc 0 [noop 1/1] x=1 y=0 z=0
+breakpoint on x == 4
+cycle 4 pc 2 [addx -5 1/2] x=4 y=0 z=0
+breakpoint at cycle 5
+cycle 5 pc 2 [addx -5 2/2] x=4 y=0 z=0
+program finished
+cycle 6 pc 3 [halted] x=-1 y=0 z=0
+"
+        );
+    }
+
+    #[test]
+    fn test_continue_stops_runaway_program() {
+        let program = "jmp 0\n".parse().unwrap();
+        let mut cpu = Cpu::with_config(Config::default());
+
+        let mut output = Vec::new();
+        run(&mut cpu, &program, "continue\n".as_bytes(), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\
+cycle 1 pc 0 [jmp 0 1/1] x=1 y=0 z=0
+stopped after 1000000 cycles
+cycle 1000001 pc 0 [jmp 0 1/1] x=1 y=0 z=0
+"
+        );
+    }
+
+    #[test]
+    fn test_runtime_errors_keep_the_session() {
+        let program = "addx 2147483646\naddx 1\nnoop\n".parse().unwrap();
+        let mut cpu = Cpu::with_config(Config::default());
+
+        let mut output = Vec::new();
+        run(
+            &mut cpu,
+            &program,
+            "next\nnext\nregs\nstep\n".as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\
+cycle 1 pc 0 [addx 2147483646 1/2] x=1 y=0 z=0
+cycle 3 pc 1 [addx 1 1/2] x=2147483647 y=0 z=0
+error: cycle 4: addx 1: register overflowed
+cycle 4 pc 1 [addx 1 2/2] x=2147483647 y=0 z=0
+cycle 4 pc 1 [addx 1 2/2] x=2147483647 y=0 z=0
+error: cycle 4: addx 1: register overflowed
+cycle 4 pc 1 [addx 1 2/2] x=2147483647 y=0 z=0
+"
+        );
+    }
+
+    #[test]
+    fn test_partial_screen() {
+        let output = debug("step 3\nscreen\nbogus\n");
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[2], format!("###{}", " ".repeat(37)));
+        assert_eq!(lines[3], " ".repeat(40));
+        assert_eq!(lines[8], "error: unknown command bogus, try help");
+    }
+}
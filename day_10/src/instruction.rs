@@ -0,0 +1,244 @@
+use std::{fmt, ops::Deref, str::FromStr};
+
+use anyhow::{bail, Context, Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    X,
+    Y,
+    Z,
+}
+
+impl Register {
+    pub const COUNT: usize = 3;
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    fn name(&self) -> char {
+        match self {
+            Register::X => 'x',
+            Register::Y => 'y',
+            Register::Z => 'z',
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "x" => Some(Register::X),
+            "y" => Some(Register::Y),
+            "z" => Some(Register::Z),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    Noop,
+    Add,
+    Mul,
+    Jmp,
+}
+
+/// Properties of an opcode. Opcodes that work on a register are written as
+/// the mnemonic followed by the register name, e.g. `addx` or `muly`.
+pub struct OpcodeInfo {
+    pub opcode: Opcode,
+    pub mnemonic: &'static str,
+    pub cycles: u32,
+    pub takes_register: bool,
+}
+
+pub const INSTRUCTION_SET: [OpcodeInfo; 4] = [
+    OpcodeInfo {
+        opcode: Opcode::Noop,
+        mnemonic: "noop",
+        cycles: 1,
+        takes_register: false,
+    },
+    OpcodeInfo {
+        opcode: Opcode::Add,
+        mnemonic: "add",
+        cycles: 2,
+        takes_register: true,
+    },
+    OpcodeInfo {
+        opcode: Opcode::Mul,
+        mnemonic: "mul",
+        cycles: 3,
+        takes_register: true,
+    },
+    OpcodeInfo {
+        opcode: Opcode::Jmp,
+        mnemonic: "jmp",
+        cycles: 1,
+        takes_register: false,
+    },
+];
+
+impl Opcode {
+    pub fn info(&self) -> &'static OpcodeInfo {
+        INSTRUCTION_SET
+            .iter()
+            .find(|info| info.opcode == *self)
+            .expect("every opcode is listed in the instruction set")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Noop,
+    Add(Register, i32),
+    Mul(Register, i32),
+    /// Jumps relative to the address of the jump itself.
+    Jmp(i32),
+}
+
+impl Instruction {
+    pub fn opcode(&self) -> Opcode {
+        match self {
+            Instruction::Noop => Opcode::Noop,
+            Instruction::Add(..) => Opcode::Add,
+            Instruction::Mul(..) => Opcode::Mul,
+            Instruction::Jmp(_) => Opcode::Jmp,
+        }
+    }
+
+    pub fn cycles(&self) -> u32 {
+        self.opcode().info().cycles
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mnemonic = self.opcode().info().mnemonic;
+
+        match self {
+            Instruction::Noop => write!(f, "{mnemonic}"),
+            Instruction::Add(register, operand) | Instruction::Mul(register, operand) => {
+                write!(f, "{mnemonic}{} {operand}", register.name())
+            }
+            Instruction::Jmp(offset) => write!(f, "{mnemonic} {offset}"),
+        }
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let word = parts.next().context("missing instruction")?;
+
+        let (info, register) = INSTRUCTION_SET
+            .iter()
+            .find_map(|info| {
+                let rest = word.strip_prefix(info.mnemonic)?;
+
+                match (info.takes_register, rest) {
+                    (false, "") => Some((info, None)),
+                    (true, name) => Some((info, Some(Register::from_name(name)?))),
+                    _ => None,
+                }
+            })
+            .with_context(|| format!("invalid instruction {word}"))?;
+
+        let operand = match (info.opcode, parts.next()) {
+            (Opcode::Noop, None) => None,
+            (Opcode::Noop, Some(_)) => bail!("noop takes no operand"),
+            (_, Some(operand)) => Some(operand.parse::<i32>()?),
+            (_, None) => bail!("missing operand"),
+        };
+
+        if parts.next().is_some() {
+            bail!("unexpected trailing input");
+        }
+
+        Ok(match (info.opcode, register, operand) {
+            (Opcode::Noop, _, _) => Instruction::Noop,
+            (Opcode::Add, Some(register), Some(operand)) => Instruction::Add(register, operand),
+            (Opcode::Mul, Some(register), Some(operand)) => Instruction::Mul(register, operand),
+            (Opcode::Jmp, _, Some(offset)) => Instruction::Jmp(offset),
+            _ => unreachable!("operands are checked against the instruction set"),
+        })
+    }
+}
+
+/// An assembled program, one instruction per line of source.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Program(Vec<Instruction>);
+
+impl Deref for Program {
+    type Target = [Instruction];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromStr for Program {
+    type Err = Error;
+
+    /// Assembles a program. Blank lines are skipped, errors name the line.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                line.parse()
+                    .with_context(|| format!("line {}: {line:?}", i + 1))
+            })
+            .collect::<Result<_>>()
+            .map(Program)
+    }
+}
+
+impl fmt::Display for Program {
+    /// Disassembles the program into source that assembles to it again.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for instruction in &self.0 {
+            writeln!(f, "{instruction}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let source = "noop\naddx 3\naddy -5\nmulz 2\njmp -3\n";
+        let program: Program = source.parse().unwrap();
+
+        assert_eq!(
+            &program[..],
+            [
+                Instruction::Noop,
+                Instruction::Add(Register::X, 3),
+                Instruction::Add(Register::Y, -5),
+                Instruction::Mul(Register::Z, 2),
+                Instruction::Jmp(-3),
+            ]
+        );
+        assert_eq!(program.to_string(), source);
+    }
+
+    #[test]
+    fn test_errors_name_the_line() {
+        for (source, line) in [
+            ("noop\naddx\n", "line 2"),
+            ("noop\n\nnoop 1\n", "line 3"),
+            ("addq 1\n", "line 1"),
+            ("noop\naddx 1 2\n", "line 2"),
+            ("jmpx 1\n", "line 1"),
+        ] {
+            let error = source.parse::<Program>().unwrap_err();
+            assert!(error.to_string().starts_with(line), "{error:#}");
+        }
+    }
+}
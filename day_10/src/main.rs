@@ -1,111 +1,547 @@
 use std::{
-    fs::File,
-    io::{self, BufRead},
-    path::Path,
-    str::FromStr,
+    fs::{self, File},
+    io::{self, BufWriter},
 };
 
-use anyhow::{bail, Context, Error, Result};
+use anyhow::{bail, Context, Result};
 
-// The output is wrapped in a Result to allow matching on errors
-// Returns an Iterator to the Reader of the lines of the file.
-fn read_lines<P>(filename: P) -> Result<io::Lines<io::BufReader<File>>>
-where
-    P: AsRef<Path>,
-{
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
-}
+mod crt;
+mod debugger;
+mod instruction;
+mod ocr;
+mod schedule;
+mod trace;
 
-#[derive(Debug)]
-enum Instruction {
-    Noop,
-    AddX(i32),
-}
+use crt::{Crt, Sprite};
+use instruction::{Instruction, Program, Register};
+use schedule::SampleSchedule;
+use trace::{Phase, TraceRecord};
 
-impl FromStr for Instruction {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.starts_with("noop") {
-            Ok(Self::Noop)
-        } else if s.starts_with("addx") {
-            let operand = s
-                .split(' ')
-                .nth(1)
-                .context("malformed instruction")?
-                .parse()?;
-            Ok(Self::AddX(operand))
-        } else {
-            bail!("Invalid operation")
-        }
-    }
+/// Cycles a program may run when `--max-cycles` is not given. Running into
+/// this limit is an error, since the program most likely never halts.
+const DEFAULT_CYCLE_LIMIT: i32 = 1_000_000;
+
+#[derive(Debug, Default)]
+struct Config {
+    samples: SampleSchedule,
+    screen: Crt,
 }
 
 #[derive(Debug)]
 struct Cpu {
-    x: i32,
+    registers: [i32; Register::COUNT],
     cycle: i32,
-    sum_of_signal_strengths: i32,
+    pc: i64,
+    /// Cycles of the current instruction that have already been executed.
+    phase: u32,
+    samples: SampleSchedule,
+    /// Signal strengths as `(cycle, strength)`, in the order they were taken.
+    signal_strengths: Vec<(i32, i32)>,
+    crt: Crt,
+    trace: Option<Vec<TraceRecord>>,
 }
 
 impl Cpu {
-    fn new() -> Self {
+    fn with_config(config: Config) -> Self {
+        let mut registers = [0; Register::COUNT];
+        registers[Register::X.index()] = 1;
+
         Cpu {
-            x: 1,
+            registers,
             cycle: 1,
-            sum_of_signal_strengths: 0,
+            pc: 0,
+            phase: 0,
+            samples: config.samples,
+            signal_strengths: Vec::new(),
+            crt: config.screen,
+            trace: None,
         }
     }
 
-    fn execute_instruction(&mut self, instruction: Instruction) {
-        self.during_cycle();
+    /// Makes the CPU keep a [`TraceRecord`] of every cycle.
+    fn traced(self) -> Self {
+        Cpu {
+            trace: Some(Vec::new()),
+            ..self
+        }
+    }
+
+    fn sum_of_signal_strengths(&self) -> i64 {
+        self.signal_strengths
+            .iter()
+            .map(|(_, strength)| i64::from(*strength))
+            .sum()
+    }
 
-        match instruction {
-            Instruction::Noop => {}
-            Instruction::AddX(i) => {
-                self.cycle += 1;
-                self.during_cycle();
+    fn x(&self) -> i32 {
+        self.registers[Register::X.index()]
+    }
+
+    /// The instruction at the program counter, if it is inside the program.
+    fn current_instruction(&self, program: &Program) -> Option<Instruction> {
+        usize::try_from(self.pc)
+            .ok()
+            .and_then(|pc| program.get(pc))
+            .copied()
+    }
 
-                self.x += i;
+    /// Runs the program until the program counter leaves it, or until
+    /// `cycle_limit` cycles have been executed. Returns `true` if the
+    /// program has finished.
+    fn run(&mut self, program: &Program, cycle_limit: Option<i32>) -> Result<bool> {
+        while cycle_limit.is_none_or(|limit| self.cycle <= limit) {
+            if !self.step_cycle(program)? {
+                return Ok(true);
             }
         }
 
-        self.cycle += 1;
+        Ok(false)
     }
 
-    fn during_cycle(&mut self) {
-        if [20, 60, 100, 140, 180, 220].contains(&self.cycle) {
-            self.sum_of_signal_strengths += self.x * self.cycle;
+    /// Executes a single cycle. Returns `false` if the program has finished.
+    fn step_cycle(&mut self, program: &Program) -> Result<bool> {
+        let Some(instruction) = self.current_instruction(program) else {
+            return Ok(false);
+        };
+
+        // Everything that may fail is worked out before the state changes,
+        // so a failed cycle leaves the CPU as it was and can be inspected.
+        let total = instruction.cycles();
+        let phase = self.phase + 1;
+
+        let next_cycle = self
+            .cycle
+            .checked_add(1)
+            .context("cycle counter overflowed")?;
+        let strength = self.signal_strength()?;
+
+        let completed = if phase == total {
+            let completed = self
+                .complete_instruction(instruction)
+                .with_context(|| format!("cycle {}: {instruction}", self.cycle))?;
+            Some(completed)
+        } else {
+            None
+        };
+
+        let x = self.x();
+
+        if let Some(trace) = &mut self.trace {
+            trace.push(TraceRecord {
+                cycle: self.cycle,
+                x,
+                instruction,
+                phase: Phase {
+                    current: phase,
+                    total,
+                },
+            });
         }
-        let col = (self.cycle - 1) % 40;
-        if col == 0 {
-            println!();
+
+        if let Some(strength) = strength {
+            self.signal_strengths.push((self.cycle, strength));
         }
 
-        if (self.x - col).abs() <= 1 {
-            print!("#");
-        } else {
-            print!(".");
+        self.crt.draw(self.cycle, x);
+
+        match completed {
+            Some((registers, offset)) => {
+                self.registers = registers;
+                self.pc += offset as i64;
+                self.phase = 0;
+            }
+            None => self.phase = phase,
+        }
+
+        self.cycle = next_cycle;
+
+        Ok(true)
+    }
+
+    /// Executes the remaining cycles of the current instruction. Returns
+    /// `false` if the program has finished.
+    fn step_instruction(&mut self, program: &Program) -> Result<bool> {
+        while self.step_cycle(program)? {
+            if self.phase == 0 {
+                return Ok(true);
+            }
         }
+
+        Ok(false)
     }
+
+    /// Works out the registers after the last cycle of an instruction, and
+    /// the offset to the next one.
+    fn complete_instruction(
+        &self,
+        instruction: Instruction,
+    ) -> Result<([i32; Register::COUNT], i32)> {
+        let mut registers = self.registers;
+
+        let offset = match instruction {
+            Instruction::Noop => 1,
+            Instruction::Add(register, operand) => {
+                let value = &mut registers[register.index()];
+                *value = value.checked_add(operand).context("register overflowed")?;
+                1
+            }
+            Instruction::Mul(register, operand) => {
+                let value = &mut registers[register.index()];
+                *value = value.checked_mul(operand).context("register overflowed")?;
+                1
+            }
+            Instruction::Jmp(offset) => offset,
+        };
+
+        Ok((registers, offset))
+    }
+
+    /// The signal strength of the current cycle, if it is sampled.
+    fn signal_strength(&self) -> Result<Option<i32>> {
+        if !self.samples.contains(self.cycle) {
+            return Ok(None);
+        }
+
+        let strength = self
+            .x()
+            .checked_mul(self.cycle)
+            .with_context(|| format!("signal strength at cycle {} overflowed", self.cycle))?;
+
+        Ok(Some(strength))
+    }
+}
+
+/// Reads the letters on the screen. Only the puzzle's own screen is known to
+/// show letters, so failing to read any other screen is not an error.
+fn read_letters(crt: &Crt, puzzle_screen: bool) -> Result<Option<String>> {
+    match ocr::read(crt) {
+        Ok(letters) => Ok(Some(letters)),
+        Err(error) if puzzle_screen => Err(error.context("failed to read the screen")),
+        Err(_) => Ok(None),
+    }
+}
+
+fn flag_value<'a>(flags: &'a [String], name: &str) -> Option<&'a str> {
+    flags
+        .iter()
+        .find_map(|flag| flag.strip_prefix(name)?.strip_prefix('='))
 }
 
-fn main() {
-    let args: Vec<String> = std::env::args().collect();
+fn main() -> Result<()> {
+    let (flags, args): (Vec<String>, Vec<String>) =
+        std::env::args().partition(|arg| arg.starts_with("--"));
     let input_file = args.get(1).expect("Input file not provided");
 
-    let mut cpu = Cpu::new();
+    let cycle_limit: Option<i32> = flag_value(&flags, "--max-cycles")
+        .map(str::parse)
+        .transpose()
+        .context("invalid cycle limit")?;
+
+    let csv_file = flag_value(&flags, "--trace-csv");
+    let json_lines_file = flag_value(&flags, "--trace-jsonl");
+
+    let samples = flag_value(&flags, "--samples")
+        .map(str::parse)
+        .transpose()
+        .context("invalid sample schedule")?
+        .unwrap_or_default();
+
+    let puzzle_screen =
+        flag_value(&flags, "--screen").is_none() && flag_value(&flags, "--sprite").is_none();
+
+    let screen = match flag_value(&flags, "--screen") {
+        Some(size) => {
+            let (width, height) = size
+                .split_once('x')
+                .context("expected screen size as <width>x<height>")?;
+            let (width, height) = (width.parse()?, height.parse()?);
+
+            if width == 0 || height == 0 {
+                bail!("screen must not be empty");
+            }
+
+            Crt::new(width, height)
+        }
+        None => Crt::default(),
+    };
+
+    let screen = match flag_value(&flags, "--sprite") {
+        Some(sprite) => screen.with_sprite(sprite.parse::<Sprite>().context("invalid sprite")?),
+        None => screen,
+    };
+
+    let mut cpu = Cpu::with_config(Config { samples, screen });
+
+    if csv_file.is_some() || json_lines_file.is_some() {
+        cpu = cpu.traced();
+    }
+
+    let program: Program = fs::read_to_string(input_file)?
+        .parse()
+        .with_context(|| format!("failed to assemble {input_file}"))?;
+
+    if flags.iter().any(|flag| flag == "--debug") {
+        debugger::run(&mut cpu, &program, io::stdin().lock(), io::stdout())?;
+    } else {
+        let finished = cpu.run(&program, Some(cycle_limit.unwrap_or(DEFAULT_CYCLE_LIMIT)))?;
+
+        if !finished && cycle_limit.is_none() {
+            bail!(
+                "program did not finish within {DEFAULT_CYCLE_LIMIT} cycles, \
+                 use --max-cycles to run it longer"
+            );
+        }
+    }
+
+    print!("{}", cpu.crt.render());
+
+    for (cycle, strength) in &cpu.signal_strengths {
+        println!("cycle {cycle}: {strength}");
+    }
+
+    println!("sum: {}", cpu.sum_of_signal_strengths());
+
+    let letters = read_letters(&cpu.crt, puzzle_screen);
+
+    let trace = cpu.trace.unwrap_or_default();
+
+    if let Some(path) = csv_file {
+        trace::write_csv(&trace, BufWriter::new(File::create(path)?))?;
+    }
+
+    if let Some(path) = json_lines_file {
+        trace::write_json_lines(&trace, BufWriter::new(File::create(path)?))?;
+    }
+
+    if let Some(letters) = letters? {
+        println!("letters: {letters}");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_traced(filename: &str) -> Cpu {
+        let program = fs::read_to_string(filename).unwrap().parse().unwrap();
 
-    if let Ok(lines) = read_lines(input_file) {
-        for line in lines.flatten() {
-            let instr = line.parse().unwrap();
+        let mut cpu = Cpu::with_config(Config::default()).traced();
+        cpu.run(&program, None).unwrap();
 
-            cpu.execute_instruction(instr);
+        cpu
+    }
+
+    #[test]
+    fn test_trace_small_program() {
+        let cpu = run_traced("input2");
+        let trace = cpu.trace.as_ref().unwrap();
+
+        let states: Vec<(i32, i32, String)> = trace
+            .iter()
+            .map(|r| (r.cycle, r.x, r.phase.to_string()))
+            .collect();
+
+        assert_eq!(
+            states,
+            [
+                (1, 1, "1/1".into()),
+                (2, 1, "1/2".into()),
+                (3, 1, "2/2".into()),
+                (4, 4, "1/2".into()),
+                (5, 4, "2/2".into()),
+            ]
+        );
+        assert_eq!(cpu.x(), -1);
+    }
+
+    #[test]
+    fn test_trace_signal_strengths() {
+        let cpu = run_traced("input3");
+        let trace = cpu.trace.as_ref().unwrap();
+
+        let samples: Vec<i64> = [20, 60, 100, 140, 180, 220]
+            .iter()
+            .map(|cycle| trace[*cycle as usize - 1].signal_strength())
+            .collect();
+
+        assert_eq!(samples, [420, 1140, 1800, 2940, 2880, 3960]);
+        assert_eq!(cpu.sum_of_signal_strengths(), 13140);
+    }
+
+    #[test]
+    fn test_sample_schedules() {
+        let program = fs::read_to_string("input3").unwrap().parse().unwrap();
+
+        let mut cpu = Cpu::with_config(Config::default());
+        cpu.run(&program, None).unwrap();
+        let default = cpu.signal_strengths;
+
+        let mut cpu = Cpu::with_config(Config {
+            samples: "20+40".parse().unwrap(),
+            ..Default::default()
+        });
+        cpu.run(&program, None).unwrap();
+
+        assert_eq!(cpu.signal_strengths, default);
+
+        let mut cpu = Cpu::with_config(Config {
+            samples: "1, 2,240".parse().unwrap(),
+            ..Default::default()
+        });
+        cpu.run(&program, None).unwrap();
+
+        assert_eq!(cpu.signal_strengths, [(1, 1), (2, 2), (240, 4080)]);
+    }
+
+    #[test]
+    fn test_trace_wide_signal_strengths() {
+        let program: Program = "addx 1073741823\nnoop\nnoop\n".parse().unwrap();
+
+        let mut cpu = Cpu::with_config(Config::default()).traced();
+        cpu.run(&program, None).unwrap();
+        let trace = cpu.trace.unwrap();
+
+        let mut csv = Vec::new();
+        trace::write_csv(&trace, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().last(), Some("4,1073741824,noop,1/1,4294967296"));
+
+        let mut json_lines = Vec::new();
+        trace::write_json_lines(&trace, &mut json_lines).unwrap();
+        let json_lines = String::from_utf8(json_lines).unwrap();
+        assert!(json_lines.contains(r#""signal_strength":3221225472"#));
+    }
+
+    #[test]
+    fn test_crt_small_program() {
+        let cpu = run_traced("input2");
+
+        assert_eq!(
+            cpu.crt.render(),
+            "\
+#####...................................
+........................................
+........................................
+........................................
+........................................
+........................................
+"
+        );
+    }
+
+    #[test]
+    fn test_crt_example_picture() {
+        let cpu = run_traced("input3");
+
+        assert_eq!(
+            cpu.crt.render(),
+            "\
+##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######.....
+"
+        );
+    }
+
+    #[test]
+    fn test_jumps_and_registers() {
+        let program = "addy 3\nmuly 4\naddx 1\njmp -1\n".parse().unwrap();
+
+        let mut cpu = Cpu::with_config(Config::default());
+        assert!(!cpu.run(&program, Some(12)).unwrap());
+
+        assert_eq!(cpu.registers, [3, 12, 0]);
+        assert_eq!(cpu.cycle, 13);
+        assert_eq!(cpu.phase, 1);
+    }
+
+    #[test]
+    fn test_runaway_programs() {
+        let program: Program = "jmp 0\n".parse().unwrap();
+
+        let mut cpu = Cpu::with_config(Config::default());
+        assert!(!cpu.run(&program, Some(DEFAULT_CYCLE_LIMIT)).unwrap());
+        assert_eq!(cpu.cycle, DEFAULT_CYCLE_LIMIT + 1);
+
+        let mut cpu = Cpu::with_config(Config::default());
+        cpu.cycle = i32::MAX - 1;
+        let error = cpu.run(&program, None).unwrap_err();
+        assert_eq!(error.to_string(), "cycle counter overflowed");
+    }
+
+    #[test]
+    fn test_register_overflow() {
+        for (source, message) in [
+            (
+                "addx 2147483646\naddx 1\n",
+                "cycle 4: addx 1: register overflowed",
+            ),
+            (
+                "addx 2\nmulx 2147483647\n",
+                "cycle 5: mulx 2147483647: register overflowed",
+            ),
+        ] {
+            let program: Program = source.parse().unwrap();
+
+            let mut cpu = Cpu::with_config(Config::default());
+            let error = cpu.run(&program, None).unwrap_err();
+            assert_eq!(format!("{error:#}"), message);
+        }
+
+        let program: Program = "addx 1073741823\nnoop\n".parse().unwrap();
+        let mut cpu = Cpu::with_config(Config {
+            samples: "3".parse().unwrap(),
+            ..Default::default()
+        });
+        let error = cpu.run(&program, None).unwrap_err();
+        assert_eq!(error.to_string(), "signal strength at cycle 3 overflowed");
+    }
+
+    #[test]
+    fn test_sprite_masks() {
+        let program: Program = fs::read_to_string("input2").unwrap().parse().unwrap();
+
+        let mut cpu = Cpu::with_config(Config {
+            screen: Crt::new(5, 1).with_sprite("#.#.#".parse().unwrap()),
+            ..Default::default()
+        });
+        cpu.run(&program, None).unwrap();
+
+        assert_eq!(cpu.crt, ".#..#".parse().unwrap());
+
+        let program: Program = "noop\nnoop\nnoop\nnoop\nnoop\n".parse().unwrap();
+
+        for (sprite, picture) in [(Sprite::default(), "###.."), (Sprite::solid(5), "####.")] {
+            let mut cpu = Cpu::with_config(Config {
+                screen: Crt::new(5, 1).with_sprite(sprite),
+                ..Default::default()
+            });
+            cpu.run(&program, None).unwrap();
+
+            assert_eq!(cpu.crt, picture.parse().unwrap());
         }
     }
 
-    println!();
+    #[test]
+    fn test_ocr() {
+        assert_eq!(ocr::read(&run_traced("input").crt).unwrap(), "EHZFZHCZ");
 
-    println!("sum: {}", cpu.sum_of_signal_strengths);
+        let error = ocr::read(&run_traced("input3").crt).unwrap_err();
+        assert!(error.to_string().starts_with("unknown glyph at position 0"));
+
+        let crt = run_traced("input3").crt;
+        assert!(read_letters(&crt, true).is_err());
+        assert_eq!(read_letters(&crt, false).unwrap(), None);
+        assert_eq!(
+            read_letters(&run_traced("input").crt, false)
+                .unwrap()
+                .as_deref(),
+            Some("EHZFZHCZ")
+        );
+    }
 }
@@ -0,0 +1,62 @@
+use anyhow::{bail, Result};
+
+use crate::crt::{Crt, HEIGHT};
+
+/// Width of a letter on the screen, without the blank spacer column.
+const GLYPH_WIDTH: usize = 4;
+
+/// Letters of the font used by the puzzles, row by row.
+const FONT: [(char, [&str; HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// Reads the capital letters drawn on the screen. Every letter occupies
+/// four columns followed by one blank column.
+pub fn read(crt: &Crt) -> Result<String> {
+    if crt.height() != HEIGHT {
+        bail!("letters need a screen that is {HEIGHT} rows high");
+    }
+
+    let rows: Vec<String> = crt.render().lines().map(str::to_owned).collect();
+
+    (0..crt.width() / (GLYPH_WIDTH + 1))
+        .map(|index| {
+            let start = index * (GLYPH_WIDTH + 1);
+
+            let glyph: Vec<&str> = rows
+                .iter()
+                .map(|row| &row[start..start + GLYPH_WIDTH])
+                .collect();
+            let spacer_is_blank = rows
+                .iter()
+                .all(|row| row.as_bytes()[start + GLYPH_WIDTH] == b'.');
+
+            match FONT.iter().find(|(_, pattern)| pattern[..] == glyph[..]) {
+                Some((letter, _)) if spacer_is_blank => Ok(*letter),
+                _ => bail!(
+                    "unknown glyph at position {index}:\n{}",
+                    rows.iter()
+                        .map(|row| &row[start..start + GLYPH_WIDTH + 1])
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                ),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,56 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Error};
+
+/// The cycles during which the signal strength is sampled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SampleSchedule {
+    Cycles(Vec<i32>),
+    /// Every `stride` cycles, beginning with cycle `start`.
+    Stride {
+        start: i32,
+        stride: i32,
+    },
+}
+
+impl Default for SampleSchedule {
+    fn default() -> Self {
+        SampleSchedule::Cycles(vec![20, 60, 100, 140, 180, 220])
+    }
+}
+
+impl SampleSchedule {
+    pub fn contains(&self, cycle: i32) -> bool {
+        match self {
+            SampleSchedule::Cycles(cycles) => cycles.contains(&cycle),
+            SampleSchedule::Stride { start, stride } => {
+                cycle >= *start && (cycle - start) % stride == 0
+            }
+        }
+    }
+}
+
+impl FromStr for SampleSchedule {
+    type Err = Error;
+
+    /// Parses either a list like `20,60,100` or a stride like `20+40`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((start, stride)) = s.split_once('+') {
+            let start = start.parse().context("invalid start cycle")?;
+            let stride = stride.parse().context("invalid stride")?;
+
+            if stride <= 0 {
+                bail!("stride must be positive");
+            }
+
+            Ok(SampleSchedule::Stride { start, stride })
+        } else {
+            let cycles = s
+                .split(',')
+                .map(|cycle| cycle.trim().parse().context("invalid cycle"))
+                .collect::<Result<_, _>>()?;
+
+            Ok(SampleSchedule::Cycles(cycles))
+        }
+    }
+}
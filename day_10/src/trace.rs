@@ -0,0 +1,69 @@
+use std::{fmt, io::Write};
+
+use anyhow::Result;
+
+use crate::instruction::Instruction;
+
+/// Position of a cycle within the instruction that is being executed,
+/// e.g. `2/2` for the second and last cycle of `addx`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Phase {
+    pub current: u32,
+    pub total: u32,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.current, self.total)
+    }
+}
+
+/// State of the CPU during a single cycle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceRecord {
+    pub cycle: i32,
+    pub x: i32,
+    pub instruction: Instruction,
+    pub phase: Phase,
+}
+
+impl TraceRecord {
+    /// Widened, since the product of two `i32` values may not fit into one.
+    pub fn signal_strength(&self) -> i64 {
+        i64::from(self.cycle) * i64::from(self.x)
+    }
+}
+
+pub fn write_csv<W: Write>(records: &[TraceRecord], mut writer: W) -> Result<()> {
+    writeln!(writer, "cycle,x,instruction,phase,signal_strength")?;
+
+    for record in records {
+        writeln!(
+            writer,
+            "{},{},{},{},{}",
+            record.cycle,
+            record.x,
+            record.instruction,
+            record.phase,
+            record.signal_strength()
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn write_json_lines<W: Write>(records: &[TraceRecord], mut writer: W) -> Result<()> {
+    for record in records {
+        writeln!(
+            writer,
+            r#"{{"cycle":{},"x":{},"instruction":"{}","phase":"{}","signal_strength":{}}}"#,
+            record.cycle,
+            record.x,
+            record.instruction,
+            record.phase,
+            record.signal_strength()
+        )?;
+    }
+
+    Ok(())
+}
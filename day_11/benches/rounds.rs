@@ -0,0 +1,59 @@
+use std::{fs, thread};
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use day_11::{parse_monkeys, MonkeyBusiness};
+
+const ROUNDS: u64 = 10000;
+
+fn bench_rounds(c: &mut Criterion) {
+    let notes = fs::read_to_string("input").unwrap();
+    let monkeys = parse_monkeys(&notes).unwrap();
+    let business = || MonkeyBusiness::new(monkeys.clone(), None).unwrap();
+
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+
+    let mut group = c.benchmark_group("10000_rounds");
+    group.sample_size(10);
+
+    group.bench_function("round_by_round", |b| {
+        b.iter_batched_ref(
+            business,
+            |business| {
+                for _ in 0..ROUNDS {
+                    business.simulate_round(&mut ()).unwrap();
+                }
+                black_box(business.ranking())
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function(format!("parallel_{threads}_threads"), |b| {
+        b.iter_batched_ref(
+            business,
+            |business| {
+                business
+                    .simulate_rounds_in_parallel(ROUNDS, threads)
+                    .unwrap();
+                black_box(business.ranking())
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("skipping_cycles", |b| {
+        b.iter_batched_ref(
+            business,
+            |business| {
+                business.simulate_rounds_skipping_cycles(ROUNDS).unwrap();
+                black_box(business.ranking())
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_rounds);
+criterion_main!(benches);
@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+
+use crate::{narrow_modulus, MonkeyBusiness, WorryLevel};
+
+/// Where an item is at the start of a round, and how worried it makes us.
+pub(crate) type ItemState = (usize, WorryLevel);
+
+impl MonkeyBusiness<WorryLevel> {
+    /// Simulates `rounds` rounds without stepping through all of them.
+    ///
+    /// Items never interact, and with the worry levels reduced modulo the
+    /// common denominator every item only has finitely many states. So the
+    /// journey of every item eventually repeats, and the inspections of the
+    /// remaining rounds can be extrapolated from a single cycle.
+    ///
+    /// Afterwards every monkey holds the same items as after calling
+    /// [`MonkeyBusiness::simulate_round`] `rounds` times, but not
+    /// necessarily in the same order.
+    pub fn simulate_rounds_skipping_cycles(&mut self, rounds: u64) -> Result<()> {
+        let modulus = narrow_modulus(self.common_denominator)
+            .context("cycles can only be skipped with modular worry levels below 2^64")?;
+
+        let items: Vec<ItemState> = self
+            .monkeys
+            .iter_mut()
+            .enumerate()
+            .flat_map(|(i, monkey)| monkey.items.drain(..).map(move |item| (i, item)))
+            .collect();
+
+        let mut inspected_items = vec![0; self.monkeys.len()];
+
+        for item in items {
+            let (monkey, worry) =
+                self.extrapolate_item(item, modulus, rounds, &mut inspected_items)?;
+            self.monkeys[monkey].items.push_back(worry);
+        }
+
+        for (monkey, inspected) in self.monkeys.iter_mut().zip(inspected_items) {
+            monkey.inspected_items = monkey
+                .inspected_items
+                .checked_add(inspected)
+                .context("too many inspections to count")?;
+        }
+
+        self.rounds += rounds;
+
+        Ok(())
+    }
+
+    /// Follows a single item for one round, counting its inspections.
+    fn item_round(
+        &self,
+        (mut monkey, mut worry): ItemState,
+        modulus: WorryLevel,
+        inspected_items: &mut [u64],
+    ) -> Result<ItemState> {
+        loop {
+            inspected_items[monkey] += 1;
+
+            let current = &self.monkeys[monkey];
+
+            worry = current
+                .action
+                .apply(&worry, Some(modulus))
+                .context("worry level overflow")?;
+
+            let target = if current.test.test(&worry) {
+                current.throw_true
+            } else {
+                current.throw_false
+            };
+
+            // Monkeys later in the order still get their turn this round.
+            let same_round = target > monkey;
+            monkey = target;
+
+            if !same_round {
+                return Ok((monkey, worry));
+            }
+        }
+    }
+
+    pub(crate) fn item_rounds(
+        &self,
+        mut state: ItemState,
+        modulus: WorryLevel,
+        rounds: u64,
+        inspected_items: &mut [u64],
+    ) -> Result<ItemState> {
+        for _ in 0..rounds {
+            state = self.item_round(state, modulus, inspected_items)?;
+        }
+
+        Ok(state)
+    }
+
+    /// Finds the cycle of an item with Brent's algorithm and returns its
+    /// state after `rounds` rounds.
+    fn extrapolate_item(
+        &self,
+        start: ItemState,
+        modulus: WorryLevel,
+        rounds: u64,
+        inspected_items: &mut [u64],
+    ) -> Result<ItemState> {
+        let mut scratch = vec![0; self.monkeys.len()];
+        let mut step = |state| self.item_round(state, modulus, &mut scratch);
+
+        // Length of the cycle.
+        let mut power = 1;
+        let mut length = 1;
+        let mut tortoise = start;
+        let mut hare = step(start)?;
+
+        while tortoise != hare {
+            if power == length {
+                tortoise = hare;
+                power *= 2;
+                length = 0;
+            }
+
+            hare = step(hare)?;
+            length += 1;
+
+            if length > rounds {
+                // No need to look for a cycle that would not be used anyway.
+                return self.item_rounds(start, modulus, rounds, inspected_items);
+            }
+        }
+
+        // Number of rounds before the cycle is entered.
+        let mut tortoise = start;
+        let mut hare = start;
+        for _ in 0..length {
+            hare = step(hare)?;
+        }
+
+        let mut offset = 0;
+        while tortoise != hare {
+            tortoise = step(tortoise)?;
+            hare = step(hare)?;
+            offset += 1;
+        }
+
+        if rounds <= offset + length {
+            return self.item_rounds(start, modulus, rounds, inspected_items);
+        }
+
+        let cycle_start = self.item_rounds(start, modulus, offset, inspected_items)?;
+
+        let mut cycle_inspections = vec![0; self.monkeys.len()];
+        self.item_rounds(cycle_start, modulus, length, &mut cycle_inspections)?;
+
+        let cycles = (rounds - offset) / length;
+        for (total, inspected) in inspected_items.iter_mut().zip(&cycle_inspections) {
+            *total = cycles
+                .checked_mul(*inspected)
+                .and_then(|inspected| total.checked_add(inspected))
+                .context("too many inspections to count")?;
+        }
+
+        self.item_rounds(
+            cycle_start,
+            modulus,
+            (rounds - offset) % length,
+            inspected_items,
+        )
+    }
+}
@@ -0,0 +1,890 @@
+use std::{cmp::Ordering, collections::VecDeque, str::FromStr};
+
+use anyhow::{anyhow, bail, Context, Error, Result};
+
+mod cycles;
+pub mod metric;
+pub mod observer;
+pub mod operation;
+mod parallel;
+pub mod snapshot;
+pub mod worry;
+
+use metric::Rank;
+use num_bigint::BigUint;
+use observer::{Inspection, Observer};
+use operation::Operation;
+use snapshot::Snapshot;
+use worry::Worry;
+
+/// Decides which monkey an item is thrown to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Test {
+    DivisibleBy(WorryLevel),
+    GreaterThan(WorryLevel),
+    EqualTo(WorryLevel),
+    Not(Box<Test>),
+    And(Box<Test>, Box<Test>),
+    Or(Box<Test>, Box<Test>),
+}
+
+impl Test {
+    pub fn test<W: Worry>(&self, number_to_test: &W) -> bool {
+        match self {
+            Test::DivisibleBy(nr) => number_to_test.is_multiple_of(*nr),
+            Test::GreaterThan(nr) => number_to_test.cmp_constant(*nr) == Ordering::Greater,
+            Test::EqualTo(nr) => number_to_test.cmp_constant(*nr) == Ordering::Equal,
+            Test::Not(test) => !test.test(number_to_test),
+            Test::And(lhs, rhs) => lhs.test(number_to_test) && rhs.test(number_to_test),
+            Test::Or(lhs, rhs) => lhs.test(number_to_test) || rhs.test(number_to_test),
+        }
+    }
+
+    /// Whether the outcome stays the same if the worry level is reduced
+    /// modulo a multiple of all divisors.
+    pub fn is_modulus_compatible(&self) -> bool {
+        match self {
+            Test::DivisibleBy(_) => true,
+            Test::GreaterThan(_) | Test::EqualTo(_) => false,
+            Test::Not(test) => test.is_modulus_compatible(),
+            Test::And(lhs, rhs) | Test::Or(lhs, rhs) => {
+                lhs.is_modulus_compatible() && rhs.is_modulus_compatible()
+            }
+        }
+    }
+
+    pub fn divisors(&self) -> Vec<WorryLevel> {
+        match self {
+            Test::DivisibleBy(nr) => vec![*nr],
+            Test::GreaterThan(_) | Test::EqualTo(_) => Vec::new(),
+            Test::Not(test) => test.divisors(),
+            Test::And(lhs, rhs) | Test::Or(lhs, rhs) => {
+                let mut divisors = lhs.divisors();
+                divisors.extend(rhs.divisors());
+                divisors
+            }
+        }
+    }
+}
+
+impl FromStr for Test {
+    type Err = Error;
+
+    /// Parses `divisible by N`, `greater than N` and `equal to N`, combined
+    /// with `not`, `and` and `or`, in order of increasing precedence.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if let Some((lhs, rhs)) = s.split_once(" or ") {
+            return Ok(Test::Or(Box::new(lhs.parse()?), Box::new(rhs.parse()?)));
+        }
+
+        if let Some((lhs, rhs)) = s.split_once(" and ") {
+            return Ok(Test::And(Box::new(lhs.parse()?), Box::new(rhs.parse()?)));
+        }
+
+        if let Some(test) = s.strip_prefix("not ") {
+            return Ok(Test::Not(Box::new(test.parse()?)));
+        }
+
+        if let Some(divisor) = s.strip_prefix("divisible by ") {
+            let divisor = divisor.parse()?;
+
+            if divisor == 0 {
+                bail!("can not test for divisibility by zero");
+            }
+
+            return Ok(Test::DivisibleBy(divisor));
+        }
+
+        if let Some(nr) = s.strip_prefix("greater than ") {
+            return Ok(Test::GreaterThan(nr.parse()?));
+        }
+
+        if let Some(nr) = s.strip_prefix("equal to ") {
+            return Ok(Test::EqualTo(nr.parse()?));
+        }
+
+        bail!("invalid test")
+    }
+}
+
+pub type WorryLevel = u128;
+
+#[derive(Debug, Clone)]
+pub struct Monkey<W = WorryLevel> {
+    pub items: VecDeque<W>,
+    pub action: Operation,
+    pub test: Test,
+    pub throw_true: usize,
+    pub throw_false: usize,
+    pub inspected_items: u64,
+}
+
+impl<W> Monkey<W> {
+    pub fn new(
+        items: impl IntoIterator<Item = W>,
+        action: Operation,
+        test: Test,
+        throw_true: usize,
+        throw_false: usize,
+    ) -> Self {
+        Monkey {
+            items: items.into_iter().collect(),
+            action,
+            test,
+            throw_true,
+            throw_false,
+            inspected_items: 0,
+        }
+    }
+}
+
+/// A line of the notes together with its number.
+type NumberedLine<'a> = (usize, &'a str);
+
+/// Reads the next line, which has to start with `prefix`, and parses the
+/// rest of it. Errors name the line.
+fn parse_field<'a, T>(
+    lines: &mut impl Iterator<Item = NumberedLine<'a>>,
+    prefix: &str,
+    parse: impl FnOnce(&str) -> Result<T>,
+) -> Result<T> {
+    parse_numbered_field(lines, prefix, parse).map(|(_, value)| value)
+}
+
+/// Like [`parse_field`], but also returns the line that was read.
+fn parse_numbered_field<'a, T>(
+    lines: &mut impl Iterator<Item = NumberedLine<'a>>,
+    prefix: &str,
+    parse: impl FnOnce(&str) -> Result<T>,
+) -> Result<(NumberedLine<'a>, T)> {
+    let (nr, line) = lines
+        .next()
+        .with_context(|| format!("unexpected end of file, expected {prefix:?}"))?;
+
+    let value = line
+        .trim_start()
+        .strip_prefix(prefix)
+        .with_context(|| format!("expected {prefix:?}"))
+        .and_then(parse)
+        .with_context(|| format!("line {nr}: {line:?}"))?;
+
+    Ok(((nr, line), value))
+}
+
+impl Monkey {
+    /// Parses the notes on the monkey with the given `id` from numbered lines.
+    /// Also returns the lines naming the targets, for later checks.
+    fn new_from_lines<'a>(
+        lines: &mut impl Iterator<Item = NumberedLine<'a>>,
+        id: usize,
+    ) -> Result<(Self, [NumberedLine<'a>; 2])> {
+        parse_field(lines, "Monkey ", |rest| {
+            let found: usize = rest.strip_suffix(':').context("missing colon")?.parse()?;
+
+            if found != id {
+                bail!("expected monkey {id}, found monkey {found}");
+            }
+
+            Ok(())
+        })?;
+
+        let items = parse_field(lines, "Starting items:", |rest| {
+            let rest = rest.trim();
+
+            if rest.is_empty() {
+                return Ok(VecDeque::new());
+            }
+
+            rest.split(',')
+                .map(|nr| Ok(nr.trim().parse()?))
+                .collect::<Result<_>>()
+        })?;
+
+        let action = parse_field(lines, "Operation:", |rest| rest.parse())?;
+        let test = parse_field(lines, "Test:", |rest| rest.parse())?;
+
+        let parse_target = |rest: &str| -> Result<usize> {
+            let target = rest.parse()?;
+
+            if target == id {
+                bail!("monkey {id} can not throw to itself");
+            }
+
+            Ok(target)
+        };
+
+        let (true_line, throw_true) =
+            parse_numbered_field(lines, "If true: throw to monkey ", parse_target)?;
+        let (false_line, throw_false) =
+            parse_numbered_field(lines, "If false: throw to monkey ", parse_target)?;
+
+        Ok((
+            Monkey::new(items, action, test, throw_true, throw_false),
+            [true_line, false_line],
+        ))
+    }
+
+    /// Converts the worry levels of the held items into another type.
+    pub fn convert<W: Worry>(self) -> Monkey<W> {
+        Monkey {
+            items: self.items.into_iter().map(W::from).collect(),
+            action: self.action,
+            test: self.test,
+            throw_true: self.throw_true,
+            throw_false: self.throw_false,
+            inspected_items: self.inspected_items,
+        }
+    }
+}
+
+/// Parses the notes on all monkeys. Blank lines between monkeys are
+/// optional, and so is the one at the end.
+pub fn parse_monkeys(notes: &str) -> Result<Vec<Monkey>> {
+    let mut lines = notes
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .peekable();
+    let mut monkeys = Vec::new();
+    let mut target_lines = Vec::new();
+
+    loop {
+        while lines.next_if(|(_, line)| line.trim().is_empty()).is_some() {}
+
+        if lines.peek().is_none() {
+            break;
+        }
+
+        let (monkey, lines) = Monkey::new_from_lines(&mut lines, monkeys.len())?;
+        monkeys.push(monkey);
+        target_lines.push(lines);
+    }
+
+    if monkeys.is_empty() {
+        bail!("no monkeys in the notes");
+    }
+
+    for (i, (monkey, lines)) in monkeys.iter().zip(&target_lines).enumerate() {
+        for (target, (nr, line)) in [monkey.throw_true, monkey.throw_false]
+            .into_iter()
+            .zip(lines)
+        {
+            if target >= monkeys.len() {
+                return Err(anyhow!(
+                    "monkey {i} throws to monkey {target}, which does not exist"
+                ))
+                .with_context(|| format!("line {nr}: {line:?}"));
+            }
+        }
+    }
+
+    Ok(monkeys)
+}
+
+/// Every monkey has to throw to another monkey that exists.
+fn check_targets<W>(monkeys: &[Monkey<W>]) -> Result<()> {
+    for (i, monkey) in monkeys.iter().enumerate() {
+        for target in [monkey.throw_true, monkey.throw_false] {
+            if target == i {
+                bail!("monkey {i} can not throw to itself");
+            }
+
+            if target >= monkeys.len() {
+                bail!("monkey {i} throws to monkey {target}, which does not exist");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Worry levels can be reduced modulo the least common multiple of all
+/// divisors only if they are not relieved, and if no test depends on anything
+/// but divisibility.
+fn common_denominator<W>(monkeys: &[Monkey<W>], relief: Option<WorryLevel>) -> Option<WorryLevel> {
+    if relief.is_some() || !monkeys.iter().all(|m| m.test.is_modulus_compatible()) {
+        return None;
+    }
+
+    monkeys
+        .iter()
+        .flat_map(|m| m.test.divisors())
+        .try_fold(1 as WorryLevel, |acc, div| {
+            (acc / gcd(acc, div)).checked_mul(div)
+        })
+}
+
+fn gcd(a: WorryLevel, b: WorryLevel) -> WorryLevel {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Largest common denominator for which modular worry levels can be kept in
+/// a [`WorryLevel`]. Reduced levels stay below it, so their products fit.
+const MAX_NARROW_MODULUS: WorryLevel = u64::MAX as WorryLevel;
+
+/// The common denominator, if reducing by it keeps the worry levels small
+/// enough for [`WorryLevel`] arithmetic.
+fn narrow_modulus(common_denominator: Option<WorryLevel>) -> Option<WorryLevel> {
+    common_denominator.filter(|modulus| *modulus <= MAX_NARROW_MODULUS)
+}
+
+/// A group of monkeys playing keep away with items of type `W`.
+pub struct MonkeyBusiness<W = WorryLevel> {
+    monkeys: Vec<Monkey<W>>,
+    common_denominator: Option<WorryLevel>,
+    /// Divisor applied after every inspection, if the worry gets relieved.
+    relief: Option<WorryLevel>,
+    /// Number of rounds played so far.
+    rounds: u64,
+}
+
+impl<W: Worry> MonkeyBusiness<W> {
+    /// Fails if a monkey throws to itself or to a monkey that does not exist.
+    pub fn new(monkeys: Vec<Monkey<W>>, relief: Option<WorryLevel>) -> Result<Self> {
+        check_targets(&monkeys)?;
+
+        let common_denominator = common_denominator(&monkeys, relief);
+
+        Ok(MonkeyBusiness {
+            monkeys,
+            common_denominator,
+            relief,
+            rounds: 0,
+        })
+    }
+
+    pub fn monkeys(&self) -> &[Monkey<W>] {
+        &self.monkeys
+    }
+
+    /// Number of rounds played so far.
+    pub fn rounds(&self) -> u64 {
+        self.rounds
+    }
+
+    pub fn snapshot(&self) -> Snapshot<W> {
+        Snapshot::new(self.rounds, &self.monkeys)
+    }
+
+    pub fn simulate_round(&mut self, observer: &mut impl Observer<W>) -> Result<()> {
+        self.rounds += 1;
+
+        for i in 0..self.monkeys.len() {
+            while let Some(item) = self.monkeys[i].items.pop_front() {
+                self.monkeys[i].inspected_items += 1;
+
+                let worry = self.monkeys[i]
+                    .action
+                    .apply(&item, self.common_denominator)
+                    .context("worry level overflow")?;
+
+                let worry = match self.relief {
+                    Some(divisor) => worry.div(divisor),
+                    None => worry,
+                };
+
+                let pos = if self.monkeys[i].test.test(&worry) {
+                    self.monkeys[i].throw_true
+                } else {
+                    self.monkeys[i].throw_false
+                };
+
+                observer.inspected(&Inspection {
+                    round: self.rounds,
+                    monkey: i,
+                    before: &item,
+                    after: &worry,
+                    target: pos,
+                });
+
+                self.monkeys[pos].items.push_back(worry);
+            }
+        }
+
+        observer.round_finished(self.rounds, &self.monkeys);
+
+        Ok(())
+    }
+
+    pub fn ranking(&self) -> Vec<Rank> {
+        metric::ranking(self.monkeys.iter().map(|m| m.inspected_items))
+    }
+}
+
+/// Simulates `rounds` rounds and returns the ranking of the monkeys. Worry
+/// levels are kept in a [`WorryLevel`] if they can be reduced modulo a
+/// common denominator of at most `u64::MAX`, and as big integers otherwise.
+///
+/// With modular worry levels the rounds are not simulated one by one, but
+/// extrapolated from the cycles of the items.
+pub fn simulate(
+    monkeys: Vec<Monkey>,
+    relief: Option<WorryLevel>,
+    rounds: u64,
+) -> Result<Vec<Rank>> {
+    if narrow_modulus(common_denominator(&monkeys, relief)).is_some() {
+        let mut business = MonkeyBusiness::new(monkeys, relief)?;
+        business.simulate_rounds_skipping_cycles(rounds)?;
+
+        Ok(business.ranking())
+    } else {
+        simulate_observed(monkeys, relief, rounds, &mut ())
+    }
+}
+
+/// Like [`simulate`], but plays every single round so `observer` sees all
+/// inspections.
+pub fn simulate_observed<O>(
+    monkeys: Vec<Monkey>,
+    relief: Option<WorryLevel>,
+    rounds: u64,
+    observer: &mut O,
+) -> Result<Vec<Rank>>
+where
+    O: Observer<WorryLevel> + Observer<BigUint>,
+{
+    fn run<W: Worry>(
+        mut business: MonkeyBusiness<W>,
+        rounds: u64,
+        observer: &mut impl Observer<W>,
+    ) -> Result<Vec<Rank>> {
+        for _ in 0..rounds {
+            business.simulate_round(observer)?;
+        }
+
+        Ok(business.ranking())
+    }
+
+    if narrow_modulus(common_denominator(&monkeys, relief)).is_some() {
+        run(MonkeyBusiness::new(monkeys, relief)?, rounds, observer)
+    } else {
+        let monkeys = monkeys
+            .into_iter()
+            .map(Monkey::convert::<BigUint>)
+            .collect();
+        run(MonkeyBusiness::new(monkeys, relief)?, rounds, observer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use metric::Metric;
+    use observer::RoundTable;
+
+    fn read_monkeys(filename: &str) -> Vec<Monkey> {
+        parse_monkeys(&fs::read_to_string(filename).unwrap()).unwrap()
+    }
+
+    fn level(filename: &str, relief: Option<WorryLevel>, rounds: u64) -> u128 {
+        let ranking = simulate(read_monkeys(filename), relief, rounds).unwrap();
+        Metric::default().level(&ranking).unwrap()
+    }
+
+    #[test]
+    fn test_example() {
+        assert_eq!(level("input2", Some(3), 20), 10605);
+        assert_eq!(level("input2", None, 10000), 2713310158);
+    }
+
+    #[test]
+    fn test_worry_policies() {
+        use worry::WorryPolicy;
+
+        let monkeys = read_monkeys("input2");
+        let mut levels = Vec::new();
+
+        for policy in ["div:3", "modular"] {
+            let policy: WorryPolicy = policy.parse().unwrap();
+            let ranking = simulate(monkeys.clone(), policy.relief(), policy.default_rounds());
+            levels.push(Metric::default().level(&ranking.unwrap()).unwrap());
+        }
+
+        assert_eq!(levels, [10605, 2713310158]);
+        assert_eq!(WorryPolicy::Divide(7).to_string(), "div:7");
+        assert_eq!(WorryPolicy::Modular.to_string(), "modular");
+
+        for policy in ["div:0", "div:x", "div", "exact"] {
+            assert!(policy.parse::<WorryPolicy>().is_err(), "{policy}");
+        }
+    }
+
+    /// Relieves the worry and then reduces it modulo the common
+    /// denominator, as the simulation did before relieved worry levels were
+    /// kept exact. Returns the inspections per monkey.
+    fn inspections_reduced_after_relief(
+        mut monkeys: Vec<Monkey>,
+        relief: WorryLevel,
+        rounds: u64,
+    ) -> Vec<u64> {
+        let modulus: WorryLevel = monkeys.iter().flat_map(|m| m.test.divisors()).product();
+
+        for _ in 0..rounds {
+            for i in 0..monkeys.len() {
+                while let Some(item) = monkeys[i].items.pop_front() {
+                    monkeys[i].inspected_items += 1;
+
+                    let worry = monkeys[i].action.apply(&item, None).unwrap() / relief % modulus;
+
+                    let pos = if monkeys[i].test.test(&worry) {
+                        monkeys[i].throw_true
+                    } else {
+                        monkeys[i].throw_false
+                    };
+
+                    monkeys[pos].items.push_back(worry);
+                }
+            }
+        }
+
+        monkeys.iter().map(|m| m.inspected_items).collect()
+    }
+
+    #[test]
+    fn test_relieved_worry_is_not_reduced() {
+        // On the example the reduction changes a throw soon after the 20
+        // rounds of the puzzle. On the input the worry levels become small
+        // enough that both agree, however long they play.
+        for (filename, rounds, exact, reduced) in [
+            ("input2", 20, vec![101, 95, 7, 105], vec![101, 95, 7, 105]),
+            (
+                "input2",
+                100,
+                vec![535, 461, 15, 544],
+                vec![535, 461, 14, 543],
+            ),
+            (
+                "input",
+                20,
+                vec![321, 57, 36, 322, 326, 328, 8, 330],
+                vec![321, 57, 36, 322, 326, 328, 8, 330],
+            ),
+            (
+                "input",
+                1000,
+                vec![17961, 57, 36, 17962, 17966, 17968, 8, 17970],
+                vec![17961, 57, 36, 17962, 17966, 17968, 8, 17970],
+            ),
+        ] {
+            let monkeys = read_monkeys(filename);
+
+            let mut business = MonkeyBusiness::new(monkeys.clone(), Some(3)).unwrap();
+            for _ in 0..rounds {
+                business.simulate_round(&mut ()).unwrap();
+            }
+
+            let inspected: Vec<u64> = business
+                .monkeys()
+                .iter()
+                .map(|m| m.inspected_items)
+                .collect();
+            assert_eq!(inspected, exact, "{filename}, {rounds} rounds");
+            assert_eq!(
+                inspections_reduced_after_relief(monkeys.clone(), 3, rounds),
+                reduced,
+                "{filename}, {rounds} rounds"
+            );
+
+            let big = simulate(monkeys, Some(3), rounds).unwrap();
+            assert_eq!(big, business.ranking());
+        }
+    }
+
+    #[test]
+    fn test_skipping_cycles_matches_rounds() {
+        for filename in ["input", "input2"] {
+            for rounds in [1, 20, 1000, 10000] {
+                let mut business = MonkeyBusiness::new(read_monkeys(filename), None).unwrap();
+                for _ in 0..rounds {
+                    business.simulate_round(&mut ()).unwrap();
+                }
+
+                let mut skipping = MonkeyBusiness::new(read_monkeys(filename), None).unwrap();
+                skipping.simulate_rounds_skipping_cycles(rounds).unwrap();
+
+                for (expected, actual) in business.monkeys.iter().zip(&skipping.monkeys) {
+                    assert_eq!(expected.inspected_items, actual.inspected_items);
+
+                    let mut expected_items = Vec::from(expected.items.clone());
+                    let mut actual_items = Vec::from(actual.items.clone());
+                    expected_items.sort();
+                    actual_items.sort();
+                    assert_eq!(expected_items, actual_items);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parallel_matches_rounds() {
+        for filename in ["input", "input2"] {
+            let mut business = MonkeyBusiness::new(read_monkeys(filename), None).unwrap();
+            for _ in 0..10000 {
+                business.simulate_round(&mut ()).unwrap();
+            }
+
+            for threads in [1, 3, 8] {
+                let mut parallel = MonkeyBusiness::new(read_monkeys(filename), None).unwrap();
+                parallel
+                    .simulate_rounds_in_parallel(10000, threads)
+                    .unwrap();
+
+                assert_eq!(parallel.rounds(), 10000);
+
+                for (expected, actual) in business.monkeys.iter().zip(&parallel.monkeys) {
+                    assert_eq!(expected.inspected_items, actual.inspected_items);
+
+                    let mut expected_items = Vec::from(expected.items.clone());
+                    let mut actual_items = Vec::from(actual.items.clone());
+                    expected_items.sort();
+                    actual_items.sort();
+                    assert_eq!(expected_items, actual_items);
+                }
+            }
+        }
+
+        let mut relieved = MonkeyBusiness::new(read_monkeys("input2"), Some(3)).unwrap();
+        assert!(relieved.simulate_rounds_in_parallel(20, 4).is_err());
+    }
+
+    #[test]
+    fn test_observed_example_rounds() {
+        #[derive(Default)]
+        struct Throws(Vec<(u64, usize, WorryLevel, WorryLevel, usize)>);
+
+        impl Observer<WorryLevel> for Throws {
+            fn inspected(&mut self, inspection: &Inspection<WorryLevel>) {
+                self.0.push((
+                    inspection.round,
+                    inspection.monkey,
+                    *inspection.before,
+                    *inspection.after,
+                    inspection.target,
+                ));
+            }
+        }
+
+        let mut business = MonkeyBusiness::new(read_monkeys("input2"), Some(3)).unwrap();
+        let mut throws = Throws::default();
+        business.simulate_round(&mut throws).unwrap();
+
+        assert_eq!(throws.0.len(), 14);
+        assert_eq!(throws.0[0], (1, 0, 79, 500, 3));
+        assert_eq!(throws.0[1], (1, 0, 98, 620, 3));
+
+        let mut table = RoundTable::default();
+        simulate_observed(read_monkeys("input2"), Some(3), 20, &mut table).unwrap();
+
+        let items: Vec<String> = table.records[..4]
+            .iter()
+            .map(|record| record.items.join(", "))
+            .collect();
+        assert_eq!(
+            items,
+            ["20, 23, 27, 26", "2080, 25, 167, 207, 401, 1046", "", ""]
+        );
+
+        let inspected_items: Vec<u64> = table.records[table.records.len() - 4..]
+            .iter()
+            .map(|record| record.inspected_items)
+            .collect();
+        assert_eq!(inspected_items, [101, 95, 7, 105]);
+
+        let mut csv = Vec::new();
+        observer::write_csv(&table.records[..2], &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "round,monkey,items,inspected_items\n\
+             1,0,20 23 27 26,2\n\
+             1,1,2080 25 167 207 401 1046,4\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_line() {
+        let notes = fs::read_to_string("input2").unwrap();
+
+        for (from, to, error) in [
+            ("79, 98", "79, x98", "line 2"),
+            ("Monkey 1:", "Monkey 2:", "line 8"),
+            ("throw to monkey 3", "throw to monkey 0", "line 6"),
+            ("old * 19", "old / 19", "line 3"),
+            ("Test: divisible by 19", "Test: odd", "line 11"),
+        ] {
+            let notes = notes.replacen(from, to, 1);
+            let err = parse_monkeys(&notes).unwrap_err();
+            assert!(err.to_string().starts_with(error), "{err:#}");
+        }
+
+        let notes = notes.replacen("throw to monkey 3", "throw to monkey 4", 1);
+        let err = parse_monkeys(&notes).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "line 6: \"    If false: throw to monkey 4\": \
+             monkey 0 throws to monkey 4, which does not exist"
+        );
+
+        let notes = fs::read_to_string("input2").unwrap();
+        let truncated = &notes[..notes.find("    If false: throw to monkey 1").unwrap()];
+        let err = parse_monkeys(truncated).unwrap_err();
+        assert!(
+            err.to_string().starts_with("unexpected end of file"),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn test_trailing_blank_line_is_optional() {
+        let notes = fs::read_to_string("input2").unwrap();
+        let monkeys = parse_monkeys(notes.trim_end()).unwrap();
+
+        assert_eq!(monkeys.len(), 4);
+        assert_eq!(monkeys[3].items, [74]);
+    }
+
+    #[test]
+    fn test_programmatic_network() {
+        use operation::Expr;
+        use snapshot::Snapshots;
+
+        let add = |constant| {
+            Operation::new(Expr::Add(
+                Box::new(Expr::Old),
+                Box::new(Expr::Constant(constant)),
+            ))
+        };
+
+        let monkeys = vec![
+            Monkey::new([1, 2], add(1), Test::DivisibleBy(2), 1, 2),
+            Monkey::new([], add(2), Test::GreaterThan(4), 2, 0),
+            Monkey::new([], add(3), Test::EqualTo(100), 0, 1),
+        ];
+
+        let mut business = MonkeyBusiness::new(monkeys, None).unwrap();
+        let mut snapshots = Snapshots::default();
+        business.simulate_round(&mut snapshots).unwrap();
+        assert_eq!(business.rounds(), 1);
+        assert_eq!(snapshots.snapshots[0], business.snapshot());
+
+        // 1 becomes 2 and goes to monkey 1, which makes it 4 and throws it
+        // back. 2 becomes 3 and goes to monkey 2, which makes it 6 and throws
+        // it to monkey 1, whose turn is already over.
+        assert_eq!(
+            serde_json::to_string(&business.snapshot()).unwrap(),
+            r#"{"round":1,"monkeys":[{"items":[4],"inspected_items":2},{"items":[6],"inspected_items":1},{"items":[],"inspected_items":1}]}"#
+        );
+
+        let monkeys = business
+            .monkeys()
+            .iter()
+            .cloned()
+            .map(Monkey::convert::<BigUint>);
+        let business = MonkeyBusiness::new(monkeys.collect(), None).unwrap();
+        assert_eq!(
+            serde_json::to_value(business.snapshot()).unwrap()["monkeys"][0]["items"],
+            serde_json::json!([[4]])
+        );
+
+        let invalid = vec![Monkey::<WorryLevel>::new(
+            [],
+            add(1),
+            Test::EqualTo(1),
+            0,
+            1,
+        )];
+        assert!(MonkeyBusiness::new(invalid, None).is_err());
+    }
+
+    #[test]
+    fn test_huge_modulus_falls_back_to_big_integers() {
+        let notes = "\
+Monkey 0:
+  Starting items: 18446744073709551629, 5
+  Operation: new = old * old
+  Test: divisible by 340282366920938463463374607431768211297
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items: 7
+  Operation: new = old * old + 1
+  Test: divisible by 1
+    If true: throw to monkey 0
+    If false: throw to monkey 0
+";
+        let monkeys = parse_monkeys(notes).unwrap();
+
+        let mut narrow = MonkeyBusiness::new(monkeys.clone(), None).unwrap();
+        let error = narrow.simulate_round(&mut ()).unwrap_err();
+        assert_eq!(error.to_string(), "worry level overflow");
+        assert!(narrow.simulate_rounds_skipping_cycles(10).is_err());
+
+        // Every item passes both monkeys once per round, except for the one
+        // that starts at monkey 1.
+        let ranking = simulate(monkeys.clone(), None, 10000).unwrap();
+        let inspected: Vec<u64> = ranking.iter().map(|rank| rank.inspected_items).collect();
+        assert_eq!(inspected, [30000, 29999]);
+
+        let mut table = RoundTable::default();
+        simulate_observed(monkeys.clone(), None, 3, &mut table).unwrap();
+        assert_eq!(
+            table.records[0].items,
+            ["50", "314627666921190112577565", "626"]
+        );
+
+        // The product of the divisors does not fit, their multiple does.
+        let notes = notes.replacen(
+            "divisible by 1\n",
+            "divisible by 340282366920938463463374607431768211297\n",
+            1,
+        );
+        let same_divisors = parse_monkeys(&notes).unwrap();
+
+        let mut expected = RoundTable::default();
+        simulate_observed(monkeys, None, 3, &mut expected).unwrap();
+        let mut table = RoundTable::default();
+        simulate_observed(same_divisors, None, 3, &mut table).unwrap();
+
+        let items = |table: &RoundTable| -> Vec<Vec<String>> {
+            table
+                .records
+                .iter()
+                .map(|record| record.items.clone())
+                .collect()
+        };
+        assert_eq!(items(&table), items(&expected));
+    }
+
+    #[test]
+    fn test_combined_tests() {
+        let test: Test = "divisible by 3 and not greater than 10 or equal to 20"
+            .parse()
+            .unwrap();
+
+        let results: Vec<bool> = [3, 6, 12, 20, 21, 5]
+            .iter()
+            .map(|nr: &WorryLevel| test.test(nr))
+            .collect();
+
+        assert_eq!(results, [true, true, false, true, false, false]);
+        assert!(!test.is_modulus_compatible());
+
+        let test: Test = "not divisible by 3 or divisible by 5".parse().unwrap();
+        assert!(test.is_modulus_compatible());
+        assert_eq!(test.divisors(), [3, 5]);
+    }
+
+    #[test]
+    fn test_input() {
+        assert_eq!(level("input", Some(3), 20), 108240);
+        assert_eq!(level("input", None, 10000), 25712998901);
+    }
+}
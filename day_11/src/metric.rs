@@ -0,0 +1,118 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Error, Result};
+
+/// How busy a monkey has been.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rank {
+    pub monkey: usize,
+    pub inspected_items: u64,
+}
+
+/// Sorts the monkeys by inspected items, busiest first. Ties are broken by
+/// the monkey ID.
+pub fn ranking(inspected_items: impl IntoIterator<Item = u64>) -> Vec<Rank> {
+    let mut ranking: Vec<Rank> = inspected_items
+        .into_iter()
+        .enumerate()
+        .map(|(monkey, inspected_items)| Rank {
+            monkey,
+            inspected_items,
+        })
+        .collect();
+
+    ranking.sort_by(|a, b| {
+        b.inspected_items
+            .cmp(&a.inspected_items)
+            .then(a.monkey.cmp(&b.monkey))
+    });
+
+    ranking
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    Product,
+    Sum,
+}
+
+impl FromStr for Aggregation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "product" => Ok(Aggregation::Product),
+            "sum" => Ok(Aggregation::Sum),
+            _ => bail!("invalid aggregation {s}, expected product or sum"),
+        }
+    }
+}
+
+/// Combines the inspected items of the busiest monkeys into the level of
+/// monkey business. The puzzle multiplies the top two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metric {
+    pub top: usize,
+    pub aggregation: Aggregation,
+}
+
+impl Default for Metric {
+    fn default() -> Self {
+        Self {
+            top: 2,
+            aggregation: Aggregation::Product,
+        }
+    }
+}
+
+impl Metric {
+    /// Fails if there are fewer than `top` monkeys, or if the result
+    /// overflows.
+    pub fn level(&self, ranking: &[Rank]) -> Result<u128> {
+        if ranking.len() < self.top {
+            bail!(
+                "can not take the top {} of {} monkeys",
+                self.top,
+                ranking.len()
+            );
+        }
+
+        let mut counts = ranking[..self.top]
+            .iter()
+            .map(|rank| u128::from(rank.inspected_items));
+
+        match self.aggregation {
+            Aggregation::Product => counts.try_fold(1u128, u128::checked_mul),
+            Aggregation::Sum => counts.try_fold(0u128, u128::checked_add),
+        }
+        .context("monkey business overflow")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics() {
+        let ranking = ranking([101, 95, 7, 105]);
+
+        let ids: Vec<usize> = ranking.iter().map(|rank| rank.monkey).collect();
+        assert_eq!(ids, [3, 0, 1, 2]);
+
+        let level = |top, aggregation| Metric { top, aggregation }.level(&ranking).unwrap();
+        assert_eq!(level(2, Aggregation::Product), 10605);
+        assert_eq!(level(3, Aggregation::Sum), 301);
+        assert_eq!(level(0, Aggregation::Product), 1);
+
+        assert!(Metric::default().level(&ranking[..1]).is_err());
+
+        let huge = super::ranking([u64::MAX; 3]);
+        let product = Metric {
+            top: 3,
+            aggregation: Aggregation::Product,
+        };
+        assert!(product.level(&huge).is_err());
+        assert!(Metric::default().level(&huge).is_ok());
+    }
+}
@@ -0,0 +1,89 @@
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::{worry::Worry, Monkey};
+
+/// A single inspection, from picking up the item to throwing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inspection<'a, W> {
+    /// Starting at 1.
+    pub round: u64,
+    pub monkey: usize,
+    pub before: &'a W,
+    /// The worry level the item is thrown with, after any relief.
+    pub after: &'a W,
+    pub target: usize,
+}
+
+/// Gets notified while the monkeys are playing. Both methods do nothing by
+/// default, so observers only implement what they are interested in.
+pub trait Observer<W> {
+    fn inspected(&mut self, _inspection: &Inspection<W>) {}
+
+    fn round_finished(&mut self, _round: u64, _monkeys: &[Monkey<W>]) {}
+}
+
+/// Observes nothing.
+impl<W> Observer<W> for () {}
+
+/// What a monkey holds at the end of a round, and how many items it has
+/// inspected so far.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundRecord {
+    pub round: u64,
+    pub monkey: usize,
+    pub items: Vec<String>,
+    pub inspected_items: u64,
+}
+
+/// Records the state of every monkey after every round, as listed in the
+/// example of the puzzle.
+#[derive(Debug, Default)]
+pub struct RoundTable {
+    pub records: Vec<RoundRecord>,
+}
+
+impl<W: Worry> Observer<W> for RoundTable {
+    fn round_finished(&mut self, round: u64, monkeys: &[Monkey<W>]) {
+        self.records
+            .extend(monkeys.iter().enumerate().map(|(i, monkey)| RoundRecord {
+                round,
+                monkey: i,
+                items: monkey.items.iter().map(|item| item.to_string()).collect(),
+                inspected_items: monkey.inspected_items,
+            }));
+    }
+}
+
+pub fn write_csv<Wr: Write>(records: &[RoundRecord], mut writer: Wr) -> Result<()> {
+    writeln!(writer, "round,monkey,items,inspected_items")?;
+
+    for record in records {
+        writeln!(
+            writer,
+            "{},{},{},{}",
+            record.round,
+            record.monkey,
+            record.items.join(" "),
+            record.inspected_items
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn write_json_lines<Wr: Write>(records: &[RoundRecord], mut writer: Wr) -> Result<()> {
+    for record in records {
+        writeln!(
+            writer,
+            r#"{{"round":{},"monkey":{},"items":[{}],"inspected_items":{}}}"#,
+            record.round,
+            record.monkey,
+            record.items.join(","),
+            record.inspected_items
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use std::{iter::Peekable, str::CharIndices, str::FromStr};
+
+use anyhow::{bail, Context, Error, Result};
+
+use crate::{worry::Worry, WorryLevel};
+
+/// An arithmetic expression over the old worry level. Only addition and
+/// multiplication are supported, as both are compatible with reducing the
+/// worry level modulo the common denominator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Old,
+    Constant(WorryLevel),
+    Add(Box<Expr>, Box<Expr>),
+    Multiply(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Evaluates the expression, reducing every intermediate result modulo
+    /// `modulus` if one is given. Returns `None` on overflow.
+    fn evaluate<W: Worry>(&self, old: &W, modulus: Option<WorryLevel>) -> Option<W> {
+        let value = match self {
+            Expr::Old => old.clone(),
+            Expr::Constant(nr) => W::from(*nr),
+            Expr::Add(lhs, rhs) => lhs
+                .evaluate(old, modulus)?
+                .checked_add(&rhs.evaluate(old, modulus)?)?,
+            Expr::Multiply(lhs, rhs) => lhs
+                .evaluate(old, modulus)?
+                .checked_mul(&rhs.evaluate(old, modulus)?)?,
+        };
+
+        Some(match modulus {
+            Some(modulus) => value.reduce(modulus),
+            None => value,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operation(Expr);
+
+impl Operation {
+    pub fn new(expr: Expr) -> Self {
+        Operation(expr)
+    }
+
+    /// Returns `None` if the new worry level can not be represented by `W`.
+    pub fn apply<W: Worry>(&self, old: &W, modulus: Option<WorryLevel>) -> Option<W> {
+        self.0.evaluate(old, modulus)
+    }
+}
+
+impl FromStr for Operation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expression = s
+            .trim_start()
+            .strip_prefix("new = ")
+            .context("invalid operation")?;
+
+        let mut parser = Parser {
+            input: expression,
+            chars: expression.char_indices().peekable(),
+        };
+
+        let expr = parser.expression()?;
+
+        if let Some((pos, ch)) = parser.peek() {
+            bail!("unexpected {ch:?} at column {}", pos + 1);
+        }
+
+        Ok(Operation(expr))
+    }
+}
+
+/// Recursive descent parser for
+///
+/// ```text
+/// expression = term { "+" term }
+/// term       = factor { "*" factor }
+/// factor     = "old" | number | "(" expression ")"
+/// ```
+struct Parser<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    fn peek(&mut self) -> Option<(usize, char)> {
+        while let Some((_, ch)) = self.chars.peek() {
+            if !ch.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+        }
+
+        self.chars.peek().copied()
+    }
+
+    fn expression(&mut self) -> Result<Expr> {
+        let mut expr = self.term()?;
+
+        while let Some((_, '+')) = self.peek() {
+            self.chars.next();
+            expr = Expr::Add(Box::new(expr), Box::new(self.term()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        let mut expr = self.factor()?;
+
+        while let Some((_, '*')) = self.peek() {
+            self.chars.next();
+            expr = Expr::Multiply(Box::new(expr), Box::new(self.factor()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn factor(&mut self) -> Result<Expr> {
+        let Some((start, ch)) = self.peek() else {
+            bail!("unexpected end of expression");
+        };
+
+        match ch {
+            '(' => {
+                self.chars.next();
+                let expr = self.expression()?;
+
+                match self.peek() {
+                    Some((_, ')')) => {
+                        self.chars.next();
+                        Ok(expr)
+                    }
+                    _ => bail!("unclosed parenthesis at column {}", start + 1),
+                }
+            }
+            '0'..='9' => {
+                let end = self.take_while(|ch| ch.is_ascii_digit());
+                Ok(Expr::Constant(self.input[start..end].parse()?))
+            }
+            'a'..='z' => {
+                let end = self.take_while(|ch| ch.is_ascii_lowercase());
+
+                match &self.input[start..end] {
+                    "old" => Ok(Expr::Old),
+                    name => bail!("unknown variable {name} at column {}", start + 1),
+                }
+            }
+            '-' | '/' | '%' => bail!(
+                "operator {ch} at column {} is not supported, \
+                 it breaks the reduction modulo the common denominator",
+                start + 1
+            ),
+            _ => bail!("unexpected {ch:?} at column {}", start + 1),
+        }
+    }
+
+    /// Consumes characters while `predicate` holds and returns the end offset.
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> usize {
+        let mut end = self.input.len();
+
+        while let Some((pos, ch)) = self.chars.peek() {
+            if !predicate(*ch) {
+                end = *pos;
+                break;
+            }
+            self.chars.next();
+        }
+
+        end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(operation: &str, old: WorryLevel) -> WorryLevel {
+        operation
+            .parse::<Operation>()
+            .unwrap()
+            .apply(&old, None)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_expressions() {
+        assert_eq!(apply("new = old * old", 7), 49);
+        assert_eq!(apply("new = old + old", 7), 14);
+        assert_eq!(apply("new = 3 * old", 7), 21);
+        assert_eq!(apply("new = old * 2 + 3", 7), 17);
+        assert_eq!(apply("new = 2 * (old + 3) * old", 7), 140);
+    }
+
+    #[test]
+    fn test_modulus_is_applied_to_intermediate_results() {
+        let operation: Operation = "new = old * old * old + 5".parse().unwrap();
+
+        let old = WorryLevel::MAX / 3;
+        assert_eq!(operation.apply(&old, None), None);
+        assert_eq!(
+            operation.apply(&old, Some(97)),
+            Some(((old % 97).pow(3) + 5) % 97)
+        );
+    }
+
+    #[test]
+    fn test_rejected_expressions() {
+        for operation in [
+            "new = old / 3",
+            "new = old - 1",
+            "new = old +",
+            "new = (old + 1",
+            "new = old 3",
+            "new = older",
+            "old * 2",
+        ] {
+            assert!(operation.parse::<Operation>().is_err(), "{operation}");
+        }
+    }
+}
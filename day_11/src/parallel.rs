@@ -0,0 +1,71 @@
+use std::thread;
+
+use anyhow::{Context, Result};
+
+use crate::{cycles::ItemState, narrow_modulus, MonkeyBusiness, WorryLevel};
+
+impl MonkeyBusiness<WorryLevel> {
+    /// Simulates `rounds` rounds with the items spread over `threads`
+    /// threads. Items never interact if the worry levels are reduced modulo
+    /// the common denominator, so every item can be followed on its own.
+    ///
+    /// The inspection counts and held items are the same as after calling
+    /// [`MonkeyBusiness::simulate_round`] `rounds` times, only the order of
+    /// the items held by a monkey may differ.
+    pub fn simulate_rounds_in_parallel(&mut self, rounds: u64, threads: usize) -> Result<()> {
+        let modulus = narrow_modulus(self.common_denominator)
+            .context("items can only be simulated apart with modular worry levels below 2^64")?;
+
+        let items: Vec<ItemState> = self
+            .monkeys
+            .iter_mut()
+            .enumerate()
+            .flat_map(|(i, monkey)| monkey.items.drain(..).map(move |item| (i, item)))
+            .collect();
+
+        let chunk_size = items.len().div_ceil(threads.max(1)).max(1);
+        let business = &*self;
+
+        let results = thread::scope(|scope| {
+            let handles: Vec<_> = items
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || -> Result<(Vec<u64>, Vec<ItemState>)> {
+                        let mut inspected_items = vec![0; business.monkeys.len()];
+
+                        let items = chunk
+                            .iter()
+                            .map(|item| {
+                                business.item_rounds(*item, modulus, rounds, &mut inspected_items)
+                            })
+                            .collect::<Result<_>>()?;
+
+                        Ok((inspected_items, items))
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("simulation thread panicked"))
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        for (inspected_items, items) in results {
+            for (monkey, inspected) in self.monkeys.iter_mut().zip(inspected_items) {
+                monkey.inspected_items = monkey
+                    .inspected_items
+                    .checked_add(inspected)
+                    .context("too many inspections to count")?;
+            }
+
+            for (monkey, worry) in items {
+                self.monkeys[monkey].items.push_back(worry);
+            }
+        }
+
+        self.rounds += rounds;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use serde::Serialize;
+
+use crate::{observer::Observer, Monkey};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MonkeySnapshot<W> {
+    pub items: Vec<W>,
+    pub inspected_items: u64,
+}
+
+/// The state of all monkeys after a round, in a form that can be serialized.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Snapshot<W> {
+    pub round: u64,
+    pub monkeys: Vec<MonkeySnapshot<W>>,
+}
+
+impl<W: Clone> Snapshot<W> {
+    pub fn new(round: u64, monkeys: &[Monkey<W>]) -> Self {
+        Self {
+            round,
+            monkeys: monkeys
+                .iter()
+                .map(|monkey| MonkeySnapshot {
+                    items: monkey.items.iter().cloned().collect(),
+                    inspected_items: monkey.inspected_items,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Takes a snapshot after every round.
+#[derive(Debug)]
+pub struct Snapshots<W> {
+    pub snapshots: Vec<Snapshot<W>>,
+}
+
+impl<W> Default for Snapshots<W> {
+    fn default() -> Self {
+        Self {
+            snapshots: Vec::new(),
+        }
+    }
+}
+
+impl<W: Clone> Observer<W> for Snapshots<W> {
+    fn round_finished(&mut self, round: u64, monkeys: &[Monkey<W>]) {
+        self.snapshots.push(Snapshot::new(round, monkeys));
+    }
+}
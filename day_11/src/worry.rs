@@ -0,0 +1,134 @@
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context, Error};
+use num_bigint::BigUint;
+
+use crate::WorryLevel;
+
+/// What happens to the worry level after a monkey has inspected an item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorryPolicy {
+    /// The worry is divided by the given number, as in part 1.
+    Divide(WorryLevel),
+    /// The worry is not relieved, so it is only kept small modulo the
+    /// common denominator, as in part 2.
+    Modular,
+}
+
+impl WorryPolicy {
+    /// The divisor applied after every inspection, if any.
+    pub fn relief(&self) -> Option<WorryLevel> {
+        match self {
+            WorryPolicy::Divide(divisor) => Some(*divisor),
+            WorryPolicy::Modular => None,
+        }
+    }
+
+    /// Number of rounds the puzzle plays with this policy.
+    pub fn default_rounds(&self) -> u64 {
+        match self {
+            WorryPolicy::Divide(_) => 20,
+            WorryPolicy::Modular => 10000,
+        }
+    }
+}
+
+impl FromStr for WorryPolicy {
+    type Err = Error;
+
+    /// Parses `div:<n>` or `modular`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "modular" => Ok(WorryPolicy::Modular),
+            Some(("div", divisor)) => {
+                let divisor = divisor.parse().context("invalid divisor")?;
+
+                if divisor == 0 {
+                    bail!("can not divide the worry level by zero");
+                }
+
+                Ok(WorryPolicy::Divide(divisor))
+            }
+            _ => bail!("invalid worry policy {s}, expected div:<n> or modular"),
+        }
+    }
+}
+
+impl Display for WorryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorryPolicy::Divide(divisor) => write!(f, "div:{divisor}"),
+            WorryPolicy::Modular => write!(f, "modular"),
+        }
+    }
+}
+
+/// Arithmetic on worry levels. Constants from the notes are always given as
+/// [`WorryLevel`], the items themselves may use a wider type.
+pub trait Worry: Clone + Display + From<WorryLevel> {
+    /// Returns `None` if the result can not be represented.
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    /// Returns `None` if the result can not be represented.
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+    fn div(&self, divisor: WorryLevel) -> Self;
+    fn rem(&self, modulus: WorryLevel) -> WorryLevel;
+    fn cmp_constant(&self, constant: WorryLevel) -> Ordering;
+
+    fn is_multiple_of(&self, divisor: WorryLevel) -> bool {
+        self.rem(divisor) == 0
+    }
+
+    fn reduce(&self, modulus: WorryLevel) -> Self {
+        Self::from(self.rem(modulus))
+    }
+}
+
+impl Worry for WorryLevel {
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        WorryLevel::checked_add(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        WorryLevel::checked_mul(*self, *other)
+    }
+
+    fn div(&self, divisor: WorryLevel) -> Self {
+        self / divisor
+    }
+
+    fn rem(&self, modulus: WorryLevel) -> WorryLevel {
+        self % modulus
+    }
+
+    fn cmp_constant(&self, constant: WorryLevel) -> Ordering {
+        self.cmp(&constant)
+    }
+}
+
+/// Exact worry levels that never overflow, for notes where the worry can
+/// not be kept small by modular reduction.
+impl Worry for BigUint {
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+
+    fn div(&self, divisor: WorryLevel) -> Self {
+        self / divisor
+    }
+
+    fn rem(&self, modulus: WorryLevel) -> WorryLevel {
+        WorryLevel::try_from(self % modulus).expect("remainder is smaller than the modulus")
+    }
+
+    fn cmp_constant(&self, constant: WorryLevel) -> Ordering {
+        self.cmp(&BigUint::from(constant))
+    }
+}
@@ -0,0 +1,61 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use day_12::Map;
+
+const SIZE: usize = 2000;
+
+const RIDGE_SPACING: usize = 50;
+
+/// Deterministic map that rises from `S` in the top left to `E` in the
+/// bottom right. Every few columns there is a ridge that can only be crossed
+/// through a single gap, which forces long detours.
+fn generate(width: usize, height: usize) -> Map {
+    let mut state: u32 = 0x2545_f491;
+    let mut random = move |bound: usize| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as usize % bound
+    };
+
+    let gaps: Vec<usize> = (0..width).map(|_| random(height)).collect();
+    let mut text = String::with_capacity((width + 1) * height);
+
+    for y in 0..height {
+        for (x, gap) in gaps.iter().enumerate() {
+            let rise = (x + y) * 26 / (width + height - 1);
+            let ridge = x % RIDGE_SPACING == RIDGE_SPACING - 1 && *gap != y;
+
+            let elevation = if ridge {
+                25
+            } else {
+                rise.saturating_sub(random(2)).min(25)
+            };
+
+            text.push(if (x, y) == (0, 0) {
+                'S'
+            } else if (x, y) == (width - 1, height - 1) {
+                'E'
+            } else {
+                (b'a' + elevation as u8) as char
+            });
+        }
+        text.push('\n');
+    }
+
+    text.parse().unwrap()
+}
+
+fn bench_search(c: &mut Criterion) {
+    let map = generate(SIZE, SIZE);
+    assert!(map.find_path().is_some(), "generated map has no path");
+
+    let mut group = c.benchmark_group("search_2000x2000");
+    group.sample_size(10);
+
+    group.bench_function("a_star", |b| b.iter(|| black_box(map.find_path())));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_search);
+criterion_main!(benches);
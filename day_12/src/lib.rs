@@ -0,0 +1,499 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, VecDeque},
+    fmt,
+    ops::Add,
+    str::FromStr,
+};
+
+use anyhow::{bail, Context, Error, Result};
+
+#[derive(Default, Ord, Eq, PartialEq, PartialOrd, Hash, Copy, Clone, Debug)]
+struct Vec2 {
+    x: i32,
+    y: i32,
+}
+
+impl Add for Vec2 {
+    type Output = Vec2;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Vec2 {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+
+const UP: Vec2 = Vec2 { x: 0, y: -1 };
+const DOWN: Vec2 = Vec2 { x: 0, y: 1 };
+const RIGHT: Vec2 = Vec2 { x: 1, y: 0 };
+const LEFT: Vec2 = Vec2 { x: -1, y: 0 };
+
+#[derive(Eq, PartialEq, Debug)]
+struct OpenSetEntry {
+    vec: Vec2,
+    /// Length of the path to `vec` the entry was pushed for.
+    g_score: i32,
+    /// Length of the shortest known path to `vec` plus the estimated
+    /// remaining distance.
+    f_score: i32,
+}
+
+impl OpenSetEntry {
+    fn new(vec: Vec2, g_score: i32, dest: Vec2) -> Self {
+        Self {
+            vec,
+            g_score,
+            f_score: g_score + heuristic(vec, dest),
+        }
+    }
+}
+
+/// Reversed, so the entry with the lowest f-score is on top of the heap.
+impl Ord for OpenSetEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .f_score
+            .cmp(&self.f_score)
+            .then_with(|| self.vec.cmp(&other.vec))
+    }
+}
+
+impl PartialOrd for OpenSetEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Start,
+    End,
+    Terrain(i32),
+}
+
+impl Field {
+    /// `S` has the elevation of `a` and `E` the elevation of `z`.
+    fn height(&self) -> i32 {
+        match self {
+            Field::Start => 0,
+            Field::End => 25,
+            Field::Terrain(height) => *height,
+        }
+    }
+
+    /// A step may go down any distance, but up by one at most.
+    fn may_enter_from(&self, from: Self) -> bool {
+        self.height() - from.height() <= 1
+    }
+}
+
+impl TryFrom<char> for Field {
+    type Error = Error;
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        Ok(match value {
+            'S' => Field::Start,
+            'E' => Field::End,
+            ch => {
+                if !ch.is_ascii_lowercase() {
+                    bail!("invalid terrain height {ch}");
+                }
+
+                let height = ch as u8 - b'a';
+
+                Field::Terrain(height as i32)
+            }
+        })
+    }
+}
+
+impl From<&Field> for char {
+    fn from(f: &Field) -> Self {
+        match f {
+            Field::Start => 'S',
+            Field::End => 'E',
+            Field::Terrain(height) => {
+                let a: u8 = (*height).try_into().unwrap();
+                (b'a' + a) as char
+            }
+        }
+    }
+}
+
+pub struct Map {
+    fields: Vec<Vec<Field>>,
+    width: usize,
+    start: Vec2,
+    destination: Vec2,
+}
+
+impl FromStr for Map {
+    type Err = Error;
+
+    /// Fails unless the map is a non-empty rectangle with exactly one `S`
+    /// and one `E`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = Vec::new();
+
+        let mut start = None;
+        let mut destination = None;
+
+        let width = s.lines().next().map_or(0, |line| line.chars().count());
+
+        for (y, line) in s.lines().enumerate() {
+            if line.chars().count() != width {
+                bail!("row {} is not {width} squares wide", y + 1);
+            }
+
+            let mut current_row = Vec::new();
+
+            for (x, ch) in line.chars().enumerate() {
+                let field = ch.try_into()?;
+                let pos = Vec2 {
+                    x: x as i32,
+                    y: y as i32,
+                };
+
+                let marker = match &field {
+                    Field::Start => Some((&mut start, 'S')),
+                    Field::End => Some((&mut destination, 'E')),
+                    Field::Terrain(_) => None,
+                };
+
+                if let Some((marker, name)) = marker {
+                    if marker.replace(pos).is_some() {
+                        bail!("second {name} in row {}", y + 1);
+                    }
+                }
+
+                current_row.push(field);
+            }
+
+            fields.push(current_row);
+        }
+
+        if width == 0 {
+            bail!("the map is empty");
+        }
+
+        Ok(Map {
+            fields,
+            width,
+            start: start.context("the map has no S")?,
+            destination: destination.context("the map has no E")?,
+        })
+    }
+}
+
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.fields {
+            for field in line {
+                let a: char = field.into();
+                write!(f, "{a}")?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Map {
+    fn index(&self, index: Vec2) -> Option<Field> {
+        if index.x < 0 || index.y < 0 {
+            None
+        } else {
+            self.fields
+                .get(index.y as usize)
+                .and_then(|v| v.get(index.x as usize))
+                .copied()
+        }
+    }
+
+    /// Position of a square on the map in a flat array, row by row.
+    fn slot(&self, pos: Vec2) -> usize {
+        pos.y as usize * self.width + pos.x as usize
+    }
+
+    /// Finds the least number of steps from `S` to `E` with A*.
+    pub fn find_path(&self) -> Option<i32> {
+        let mut open_set: BinaryHeap<OpenSetEntry> = BinaryHeap::new();
+
+        open_set.push(OpenSetEntry::new(self.start, 0, self.destination));
+
+        let mut g_score = vec![i32::MAX; self.width * self.fields.len()];
+        g_score[self.slot(self.start)] = 0;
+
+        while let Some(current) = open_set.pop() {
+            let current_g_score = g_score[self.slot(current.vec)];
+
+            // A shorter path has been found since the entry was pushed, so
+            // the square has already been expanded with that one.
+            if current.g_score > current_g_score {
+                continue;
+            }
+
+            if current.vec == self.destination {
+                return Some(current_g_score);
+            }
+
+            let current_field = self.index(current.vec).unwrap();
+
+            for direction in [UP, DOWN, LEFT, RIGHT] {
+                let neighbor_pos = current.vec + direction;
+                let Some(neighbor) = self.index(neighbor_pos) else {
+                    continue;
+                };
+
+                if !neighbor.may_enter_from(current_field) {
+                    continue;
+                }
+
+                let tentative_g_score = current_g_score + 1;
+                let neighbor_slot = self.slot(neighbor_pos);
+
+                if tentative_g_score < g_score[neighbor_slot] {
+                    g_score[neighbor_slot] = tentative_g_score;
+
+                    // The entry of a longer path stays in the heap and gets
+                    // skipped when it is popped.
+                    open_set.push(OpenSetEntry::new(
+                        neighbor_pos,
+                        tentative_g_score,
+                        self.destination,
+                    ));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl Map {
+    /// Finds the least number of steps to `E` from any square of elevation
+    /// `a`, including `S`. Instead of searching from every candidate, a
+    /// single breadth-first search walks back from `E`, stepping only to
+    /// squares `E` could be reached from.
+    pub fn find_best_start(&self) -> Option<i32> {
+        let mut steps = vec![i32::MAX; self.width * self.fields.len()];
+        steps[self.slot(self.destination)] = 0;
+
+        let mut queue = VecDeque::from([self.destination]);
+
+        while let Some(current) = queue.pop_front() {
+            let current_steps = steps[self.slot(current)];
+            let current_field = self.index(current).unwrap();
+
+            if matches!(current_field, Field::Start | Field::Terrain(0)) {
+                return Some(current_steps);
+            }
+
+            for direction in [UP, DOWN, LEFT, RIGHT] {
+                let previous_pos = current + direction;
+                let Some(previous) = self.index(previous_pos) else {
+                    continue;
+                };
+
+                let previous_slot = self.slot(previous_pos);
+
+                if current_field.may_enter_from(previous) && steps[previous_slot] == i32::MAX {
+                    steps[previous_slot] = current_steps + 1;
+                    queue.push_back(previous_pos);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// The Manhattan distance. Every step moves by one square at most, so it
+/// never overestimates the remaining steps.
+fn heuristic(pos: Vec2, dest: Vec2) -> i32 {
+    (pos.x - dest.x).abs() + (pos.y - dest.y).abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs};
+
+    use super::*;
+
+    /// Elevation of a square as drawn on the map.
+    fn elevation(ch: char) -> i32 {
+        match ch {
+            'S' => 0,
+            'E' => 25,
+            ch => (ch as u8 - b'a') as i32,
+        }
+    }
+
+    /// Reference search that visits the squares in order of their distance.
+    fn bfs(map: &Map, start: Vec2) -> Option<i32> {
+        let mut steps = HashMap::from([(start, 0)]);
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(current) = queue.pop_front() {
+            if current == map.destination {
+                return Some(steps[&current]);
+            }
+
+            let current_elevation = elevation((&map.index(current).unwrap()).into());
+
+            for direction in [UP, DOWN, LEFT, RIGHT] {
+                let neighbor_pos = current + direction;
+
+                match map.index(neighbor_pos) {
+                    Some(neighbor)
+                        if elevation((&neighbor).into()) <= current_elevation + 1
+                            && !steps.contains_key(&neighbor_pos) =>
+                    {
+                        steps.insert(neighbor_pos, steps[&current] + 1);
+                        queue.push_back(neighbor_pos);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        None
+    }
+
+    /// A map that rises from `S` in the top left to `E` in the bottom right,
+    /// with some noise so the shortest path has to find its way around.
+    fn random_map(seed: u32) -> Map {
+        let mut state = seed.max(1);
+        let mut random = move |bound: u32| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state % bound
+        };
+
+        let width = 2 + random(40) as usize;
+        let height = 2 + random(40) as usize;
+        let destination = width * height - 1;
+
+        let mut text = String::new();
+        for y in 0..height {
+            for x in 0..width {
+                let position = y * width + x;
+                let rise = ((x + y) * 26 / (width + height - 1)) as i32;
+                let elevation = (rise - random(2) as i32).clamp(0, 25);
+
+                text.push(if position == destination {
+                    'E'
+                } else if position == 0 {
+                    'S'
+                } else {
+                    (b'a' + elevation as u8) as char
+                });
+            }
+            text.push('\n');
+        }
+
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_matches_bfs_on_inputs() {
+        for filename in ["input", "input2"] {
+            let map: Map = fs::read_to_string(filename).unwrap().parse().unwrap();
+            assert_eq!(map.find_path(), bfs(&map, map.start), "{filename}");
+        }
+
+        let map: Map = fs::read_to_string("input2").unwrap().parse().unwrap();
+        assert_eq!(map.find_path(), Some(31));
+
+        assert!("Sab\nbE\n".parse::<Map>().is_err());
+    }
+
+    #[test]
+    fn test_invalid_maps() {
+        for (map, error) in [
+            ("", "the map is empty"),
+            ("\n", "the map is empty"),
+            ("abc\nbcE\n", "the map has no S"),
+            ("Sbc\nbcd\n", "the map has no E"),
+            ("SbS\nbcE\n", "second S in row 1"),
+            ("Sbc\nEcE\n", "second E in row 2"),
+            ("SbE\nbc\n", "row 2 is not 3 squares wide"),
+        ] {
+            let err = map.parse::<Map>().err().unwrap();
+            assert_eq!(err.to_string(), error, "{map:?}");
+        }
+    }
+
+    #[test]
+    fn test_start_and_end_have_elevations() {
+        // `E` is as high as `z`, so it can be entered from `y`, and `S` is
+        // as low as `a`, so it can step up to `b`.
+        let map: Map = "SbcdefghijklmnopqrstuvwxyE\n".parse().unwrap();
+        assert_eq!(map.find_path(), Some(25));
+        assert_eq!(bfs(&map, map.start), Some(25));
+
+        let map: Map = "Sbz\nzzE\n".parse().unwrap();
+        assert_eq!(map.find_path(), None);
+
+        let map: Map = "Sbcdefghijklmnopqrstuvwxyz\nyyyyyyyyyyyyyyyyyyyyyyyyEz\n"
+            .parse()
+            .unwrap();
+        assert_eq!(map.find_path(), Some(25));
+        assert_eq!(map.find_best_start(), Some(25));
+    }
+
+    /// Searches from every square of elevation `a` on its own.
+    fn best_start_by_brute_force(map: &Map) -> Option<i32> {
+        map.fields
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter().enumerate().filter_map(move |(x, field)| {
+                    matches!(field, Field::Start | Field::Terrain(0)).then_some(Vec2 {
+                        x: x as i32,
+                        y: y as i32,
+                    })
+                })
+            })
+            .filter_map(|start| bfs(map, start))
+            .min()
+    }
+
+    #[test]
+    fn test_best_start() {
+        let map: Map = fs::read_to_string("input2").unwrap().parse().unwrap();
+        assert_eq!(map.find_best_start(), Some(29));
+
+        let map: Map = fs::read_to_string("input").unwrap().parse().unwrap();
+        assert_eq!(map.find_best_start(), best_start_by_brute_force(&map));
+
+        for seed in 0..100 {
+            let map = random_map(seed);
+            assert_eq!(
+                map.find_best_start(),
+                best_start_by_brute_force(&map),
+                "\n{map}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_matches_bfs_on_random_maps() {
+        let mut found = 0;
+
+        for seed in 0..500 {
+            let map = random_map(seed);
+            let steps = map.find_path();
+            assert_eq!(steps, bfs(&map, map.start), "\n{map}");
+
+            found += steps.is_some() as usize;
+        }
+
+        assert!(found > 50, "only {found} maps have a path");
+    }
+}